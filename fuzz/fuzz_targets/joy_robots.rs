@@ -0,0 +1,54 @@
+#![no_main]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use libfuzzer_sys::fuzz_target;
+use p9n_interface_2024_core::clock::Clock;
+use p9n_interface_2024_core::commands::PointCommand;
+use p9n_interface_2024_core::controller::ControllerState;
+use p9n_interface_2024_core::feedback::ControllerFeedback;
+use p9n_interface_2024_core::harness::Harness;
+use p9n_interface_2024_core::script::Script;
+use p9n_interface_2024_core::sink::Sink;
+use p9n_interface_2024_core::{robot1, robot2_1, robot2_2, robot2_3, robot2_4};
+
+/// Fails the run when a servo field leaves 25..=125 once commanded.
+struct ServoCheck(&'static [usize]);
+
+impl Sink<PointCommand> for ServoCheck {
+    fn send(&self, command: &PointCommand) {
+        let fields = command.values();
+        for &index in self.0 {
+            let value = fields[index];
+            assert!(value == 0 || (25..=125).contains(&value), "md{} = {}", index, value);
+        }
+    }
+}
+
+// A session of Joy messages, each held for up to ~0.6 s, played into every
+// robot behaviour.
+fuzz_target!(|frames: Vec<(Vec<i32>, Vec<f32>, u8)>| {
+    let script = frames.iter().fold(Script::new(), |script, (buttons, axes, hold)| {
+        script
+            .state(ControllerState::from_joy_parts(buttons, axes))
+            .wait(Duration::from_millis(*hold as u64 * 2))
+    });
+    let harness = Harness::new();
+    let feedback = Rc::new(RefCell::new(ControllerFeedback::new_at(harness.clock().now())));
+    harness.run(
+        &mut robot1::Robot1::new_at(
+            harness.sink("/md_driver_topic"),
+            harness.sink("/sd_driver_topic"),
+            harness.sink("/air_status"),
+            feedback.clone(),
+            harness.clock().now(),
+        ),
+        &script,
+    );
+    harness.run(&mut robot2_1::Robot2_1::new(Box::new(ServoCheck(&[3]))), &script);
+    harness.run(&mut robot2_2::Robot2_2::new(Box::new(ServoCheck(&[0, 1, 2, 3])), feedback), &script);
+    harness.run(&mut robot2_3::Robot2_3::new(Box::new(ServoCheck(&[2]))), &script);
+    harness.run(&mut robot2_4::Robot2_4::new(Box::new(ServoCheck(&[0, 1, 3]))), &script);
+});
@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use p9n_interface_2024_core::controller::ControllerState;
+use p9n_interface_2024_core::joint::trigger_command;
+use p9n_interface_2024_core::p9n_interface::PlaystationInterface;
+
+// Joy arrays of any length and content must decode without panicking into a
+// state the accessors can rely on.
+fuzz_target!(|input: (Vec<i32>, Vec<f32>)| {
+    let (buttons, axes) = input;
+    let state = ControllerState::from_joy_parts(&buttons, &axes);
+    assert!(state.axes.iter().all(|axis| (-1.0..=1.0).contains(axis)));
+
+    let p9n = PlaystationInterface::from_state(state);
+    assert!(!(p9n.pressed_dpad_left() && p9n.pressed_dpad_right()));
+    assert!(!(p9n.pressed_dpad_up() && p9n.pressed_dpad_down()));
+    assert!((0.0..=1.0).contains(&trigger_command(p9n.pressed_l2_analog())));
+    assert!((0.0..=1.0).contains(&trigger_command(p9n.pressed_r2_analog())));
+});
@@ -0,0 +1,132 @@
+use std::cell::{Cell, RefCell};
+use std::io::BufRead;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use p9n_interface_2024_core::clock::ros_clock;
+use p9n_interface_2024_core::controller::{ControllerState, INPUT_POLL_PERIOD};
+use p9n_interface_2024_core::joy_log::{LogWriter, Player, Recording, Speed};
+
+use safe_drive::{context::Context, error::DynError, logger::Logger, msg::common_interfaces::sensor_msgs, pr_info};
+
+const USAGE: &str = "usage: joy_log record <file>
+       joy_log replay <file> [--speed <factor> | --step] [--loop] [--seek <seconds>]";
+
+fn main() -> Result<(), DynError> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["record", file] => record(file),
+        ["replay", file, options @ ..] => replay(file, options),
+        _ => Err(USAGE.into()),
+    }
+}
+
+/// Subscribes to `joy` and appends every message to `file` until the node
+/// is interrupted. The IMU and touchpad topics are not recorded.
+fn record(file: &str) -> Result<(), DynError> {
+    let ctx = Context::new()?;
+    let node = ctx.create_node("p9n_joy_recorder", None, Default::default())?;
+    let subscriber = node.create_subscriber::<sensor_msgs::msg::Joy>("joy", None)?;
+    let mut selector = ctx.create_selector()?;
+    let clock = ros_clock(&node, &mut selector)?;
+    let logger = Logger::new("p9n_interface_2024");
+
+    let mut writer = LogWriter::create(file)?;
+    let frames = Rc::new(Cell::new(0usize));
+    let frames_sub = frames.clone();
+    selector.add_subscriber(
+        subscriber,
+        Box::new(move |msg| {
+            if writer.record_at(&ControllerState::from_joy(&msg), clock.now()).is_ok() {
+                frames_sub.set(frames_sub.get() + 1);
+            }
+        }),
+    );
+    pr_info!(logger, "recording joy to {}", file);
+    while selector.wait().is_ok() {}
+    pr_info!(logger, "recorded {} frames", frames.get());
+    Ok(())
+}
+
+/// Publishes a recording on `joy`. With `--step` each Enter on stdin plays
+/// one frame.
+fn replay(file: &str, options: &[&str]) -> Result<(), DynError> {
+    let mut speed = Speed::Scaled(1.0);
+    let mut looped = false;
+    let mut seek = Duration::ZERO;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match *option {
+            "--step" => speed = Speed::Step,
+            "--loop" => looped = true,
+            "--speed" => {
+                let factor: f64 = options.next().ok_or(USAGE)?.parse()?;
+                if factor <= 0.0 {
+                    return Err("--speed must be positive".into());
+                }
+                speed = Speed::Scaled(factor);
+            }
+            "--seek" => seek = Duration::from_secs_f64(options.next().ok_or(USAGE)?.parse()?),
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    let recording = Recording::load(file)?;
+    let ctx = Context::new()?;
+    let node = ctx.create_node("p9n_joy_player", None, Default::default())?;
+    let publisher = node.create_publisher::<sensor_msgs::msg::Joy>("joy", None)?;
+    let mut selector = ctx.create_selector()?;
+    let clock = ros_clock(&node, &mut selector)?;
+    let logger = Logger::new("p9n_interface_2024");
+    pr_info!(
+        logger,
+        "replaying {} frames ({:.1} s) from {}",
+        recording.frames.len(),
+        recording.duration().as_secs_f64(),
+        file
+    );
+
+    let mut player = Player::new(recording).with_speed(speed).with_loop(looped);
+    player.seek(seek);
+    let player = Rc::new(RefCell::new(player));
+
+    let (step_sender, steps) = mpsc::channel();
+    if speed == Speed::Step {
+        std::thread::spawn(move || {
+            for _ in std::io::stdin().lock().lines() {
+                if step_sender.send(()).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    let running = Rc::new(Cell::new(true));
+    let running_timer = running.clone();
+    selector.add_wall_timer(
+        "replay",
+        INPUT_POLL_PERIOD,
+        Box::new(move || {
+            let mut player = player.borrow_mut();
+            while steps.try_recv().is_ok() {
+                let position = player.position();
+                if let Some(state) = player.step() {
+                    pr_info!(logger, "step at {:.3} s", position.as_secs_f64());
+                    let _ = publisher.send(&state.to_joy());
+                }
+            }
+            while let Some(state) = player.poll_at(clock.now()) {
+                let _ = publisher.send(&state.to_joy());
+            }
+            if player.is_finished() {
+                running_timer.set(false);
+            }
+        }),
+    );
+
+    while running.get() {
+        selector.wait()?;
+    }
+    Ok(())
+}
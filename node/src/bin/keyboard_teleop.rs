@@ -0,0 +1,107 @@
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use p9n_interface_2024_core::clock::ros_clock;
+use p9n_interface_2024_core::keyboard::{Key, KeyParser, KeyboardState};
+
+use safe_drive::{context::Context, error::DynError, msg::common_interfaces::sensor_msgs};
+
+const PUBLISH_PERIOD: Duration = Duration::from_millis(20);
+const CTRL_C: u8 = 0x03;
+
+/// Puts the terminal into raw mode with `stty` and restores it on drop.
+struct RawTerminal {
+    saved: String,
+}
+
+impl RawTerminal {
+    fn enable() -> io::Result<RawTerminal> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        Ok(RawTerminal { saved: saved.trim().to_string() })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty").args(args).stdin(Stdio::inherit()).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed; is stdin a terminal?"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Redraws the legend in place. Raw mode needs explicit carriage returns.
+fn draw(keyboard: &KeyboardState) {
+    let mut out = io::stderr();
+    let _ = write!(out, "\x1b[H\x1b[2J");
+    let _ = write!(out, "keyboard teleop: publishing joy, Ctrl-C to quit\r\n");
+    let _ = write!(out, "lowercase holds, uppercase latches (*)\r\n\r\n");
+    let _ = write!(out, "{}", keyboard.legend().replace('\n', "\r\n"));
+    let _ = out.flush();
+}
+
+fn main() -> Result<(), DynError> {
+    let ctx = Context::new()?;
+    let node = ctx.create_node("p9n_keyboard_teleop", None, Default::default())?;
+    let publisher = node.create_publisher::<sensor_msgs::msg::Joy>("joy", None)?;
+    let mut selector = ctx.create_selector()?;
+    let clock = ros_clock(&node, &mut selector)?;
+
+    let _raw = RawTerminal::enable()?;
+    let (sender, keys) = mpsc::channel::<Option<Key>>();
+    std::thread::spawn(move || {
+        let mut parser = KeyParser::new();
+        let mut stdin = io::stdin().lock();
+        let mut buf = [0; 1];
+        while let Ok(1) = stdin.read(&mut buf) {
+            let byte = buf[0];
+            if byte == CTRL_C {
+                break;
+            }
+            if let Some(key) = parser.push(byte) {
+                if sender.send(Some(key)).is_err() {
+                    return;
+                }
+            }
+        }
+        let _ = sender.send(None);
+    });
+
+    let mut keyboard = KeyboardState::from_env();
+    draw(&keyboard);
+    let running = Rc::new(Cell::new(true));
+    let running_timer = running.clone();
+    selector.add_wall_timer(
+        "publish",
+        PUBLISH_PERIOD,
+        Box::new(move || {
+            let now = clock.now();
+            while let Ok(key) = keys.try_recv() {
+                match key {
+                    Some(key @ Key::Char(c)) if c.is_ascii_uppercase() => {
+                        keyboard.key_at(key, now);
+                        draw(&keyboard);
+                    }
+                    Some(key) => keyboard.key_at(key, now),
+                    None => running_timer.set(false),
+                }
+            }
+            let _ = publisher.send(&keyboard.state_at(now).to_joy());
+        }),
+    );
+
+    while running.get() {
+        selector.wait()?;
+    }
+    Ok(())
+}
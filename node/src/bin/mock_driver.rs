@@ -0,0 +1,39 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use p9n_interface_2024::mock_driver::add_mock_subscribers;
+use p9n_interface_2024_core::clock::ros_clock;
+use p9n_interface_2024_core::mock_driver::MockDriver;
+
+use safe_drive::{context::Context, error::DynError, logger::Logger, pr_info};
+
+const SUMMARY_PERIOD: Duration = Duration::from_millis(500);
+
+fn main() -> Result<(), DynError> {
+    let ctx = Context::new()?;
+    let node = ctx.create_node("p9n_mock_driver", None, Default::default())?;
+    let mut selector = ctx.create_selector()?;
+    let logger = Logger::new("p9n_interface_2024");
+    let clock = ros_clock(&node, &mut selector)?;
+
+    let driver = Rc::new(RefCell::new(MockDriver::new_at(clock.now())));
+    add_mock_subscribers(&node, &mut selector, driver.clone(), clock)?;
+
+    let mut last_summary = String::new();
+    selector.add_wall_timer(
+        "summary",
+        SUMMARY_PERIOD,
+        Box::new(move || {
+            let summary = driver.borrow().summary();
+            if summary != last_summary {
+                pr_info!(logger, "\n{}", summary);
+                last_summary = summary;
+            }
+        }),
+    );
+
+    loop {
+        selector.wait()?;
+    }
+}
@@ -0,0 +1,44 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use p9n_interface_2024::msgs::{md_msg, sd_msg, string_msg};
+use p9n_interface_2024_core::behaviour::add_behaviour;
+use p9n_interface_2024_core::clock::ros_clock;
+use p9n_interface_2024_core::feedback::{add_feedback_timer, feedback_topic, ControllerFeedback};
+use p9n_interface_2024_core::robot1::Robot1;
+use p9n_interface_2024_core::sink::PublisherSink;
+
+use safe_drive::{
+    context::Context,
+    error::DynError,
+    msg::common_interfaces::{sensor_msgs, std_msgs},
+};
+use drobo_interfaces::msg::{MdLibMsg, SdLibMsg};
+
+fn main() -> Result<(), DynError> {
+    let ctx = Context::new()?;
+    let node = ctx.create_node("p9n_robot1", None, Default::default())?;
+
+    let mut selector = ctx.create_selector()?;
+    let clock = ros_clock(&node, &mut selector)?;
+    let subscriber = node.create_subscriber::<sensor_msgs::msg::Joy>("joy", None)?;
+
+    let md_publisher = node.create_publisher::<MdLibMsg>("/md_driver_topic", None)?;
+    let sd_publisher = node.create_publisher::<SdLibMsg>("/sd_driver_topic", None)?;
+    let air_publisher = node.create_publisher::<std_msgs::msg::String>("/air_status", None)?;
+    let feedback_publisher = node.create_publisher::<sensor_msgs::msg::JoyFeedbackArray>(&feedback_topic(), None)?;
+
+    let feedback = Rc::new(RefCell::new(ControllerFeedback::new_at(clock.now())));
+    add_feedback_timer(&mut selector, feedback.clone(), feedback_publisher, clock.clone());
+    let robot = Robot1::new_at(
+        Box::new(PublisherSink::new(md_publisher, md_msg)),
+        Box::new(PublisherSink::new(sd_publisher, sd_msg)),
+        Box::new(PublisherSink::new(air_publisher, |status: &String| string_msg(status))),
+        feedback,
+        clock.now(),
+    );
+    add_behaviour(&node, &mut selector, subscriber, Rc::new(RefCell::new(robot)), clock)?;
+    loop {
+        selector.wait()?;
+    }
+}
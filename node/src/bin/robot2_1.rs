@@ -0,0 +1,28 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use p9n_interface_2024::msgs::point_msg;
+use p9n_interface_2024_core::behaviour::add_behaviour;
+use p9n_interface_2024_core::clock::ros_clock;
+use p9n_interface_2024_core::robot2_1::{Robot2_1, TOPIC};
+use p9n_interface_2024_core::sink::PublisherSink;
+
+use safe_drive::{context::Context, error::DynError, msg::common_interfaces::sensor_msgs};
+use drobo_interfaces::msg::PointDrive;
+
+fn main() -> Result<(), DynError> {
+    let ctx = Context::new()?;
+    let node = ctx.create_node("p9n_robot2_1", None, Default::default())?;
+
+    let mut selector = ctx.create_selector()?;
+    let clock = ros_clock(&node, &mut selector)?;
+    let subscriber = node.create_subscriber::<sensor_msgs::msg::Joy>("joy", None)?;
+
+    let robot2_1_publisher = node.create_publisher::<PointDrive>(TOPIC, None)?;
+
+    let robot = Robot2_1::new(Box::new(PublisherSink::new(robot2_1_publisher, point_msg)));
+    add_behaviour(&node, &mut selector, subscriber, Rc::new(RefCell::new(robot)), clock)?;
+    loop {
+        selector.wait()?;
+    }
+}
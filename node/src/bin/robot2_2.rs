@@ -0,0 +1,50 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use p9n_interface_2024::msgs::point_msg;
+use p9n_interface_2024_core::behaviour::add_behaviour;
+use p9n_interface_2024_core::clock::ros_clock;
+use p9n_interface_2024_core::feedback::{add_feedback_timer, feedback_topic, ControllerFeedback};
+use p9n_interface_2024_core::persist::{StartupPolicy, StateFile};
+use p9n_interface_2024_core::pose::PoseMemory;
+use p9n_interface_2024_core::robot2_2::{Robot2_2, POSE_FILE, POSE_SLOTS, TOPIC};
+use p9n_interface_2024_core::sink::PublisherSink;
+
+use safe_drive::{
+    context::Context, error::DynError, logger::Logger, msg::common_interfaces::sensor_msgs, pr_warn,
+};
+use drobo_interfaces::msg::PointDrive;
+
+fn main() -> Result<(), DynError> {
+    let ctx = Context::new()?;
+    let node = ctx.create_node("p9n_robot2_2", None, Default::default())?;
+
+    let mut selector = ctx.create_selector()?;
+    let clock = ros_clock(&node, &mut selector)?;
+    let subscriber = node.create_subscriber::<sensor_msgs::msg::Joy>("joy", None)?;
+
+    let robot2_2_publisher = node.create_publisher::<PointDrive>(TOPIC, None)?;
+    let feedback_publisher = node.create_publisher::<sensor_msgs::msg::JoyFeedbackArray>(&feedback_topic(), None)?;
+    let logger = Logger::new("p9n_interface_2024");
+
+    let feedback = Rc::new(RefCell::new(ControllerFeedback::new_at(clock.now())));
+    add_feedback_timer(&mut selector, feedback.clone(), feedback_publisher, clock.clone());
+
+    let pose_file = std::env::var("P9N_POSE_FILE").unwrap_or(POSE_FILE.to_string());
+    let poses = PoseMemory::load(&pose_file, POSE_SLOTS).unwrap_or_else(|e| {
+        pr_warn!(logger, "could not read {}: {}", pose_file, e);
+        PoseMemory::new(POSE_SLOTS)
+    });
+    let mut robot = Robot2_2::new(Box::new(PublisherSink::new(robot2_2_publisher, point_msg)), feedback).with_poses(poses);
+    if let Some(state) = StateFile::from_env() {
+        let policy = StartupPolicy::from_env().unwrap_or_else(|e| {
+            pr_warn!(logger, "{}", e);
+            StartupPolicy::Resume
+        });
+        robot = robot.with_state_file_at(state, policy, clock.now());
+    }
+    add_behaviour(&node, &mut selector, subscriber, Rc::new(RefCell::new(robot)), clock)?;
+    loop {
+        selector.wait()?;
+    }
+}
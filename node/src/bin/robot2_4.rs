@@ -0,0 +1,39 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use p9n_interface_2024::msgs::point_msg;
+use p9n_interface_2024_core::behaviour::add_behaviour;
+use p9n_interface_2024_core::clock::ros_clock;
+use p9n_interface_2024_core::persist::{StartupPolicy, StateFile};
+use p9n_interface_2024_core::robot2_4::{Robot2_4, TOPIC};
+use p9n_interface_2024_core::sink::PublisherSink;
+
+use safe_drive::{
+    context::Context, error::DynError, logger::Logger, msg::common_interfaces::sensor_msgs, pr_warn,
+};
+use drobo_interfaces::msg::PointDrive;
+
+fn main() -> Result<(), DynError> {
+    let ctx = Context::new()?;
+    let node = ctx.create_node("p9n_robot2_4", None, Default::default())?;
+
+    let mut selector = ctx.create_selector()?;
+    let clock = ros_clock(&node, &mut selector)?;
+    let subscriber = node.create_subscriber::<sensor_msgs::msg::Joy>("joy", None)?;
+
+    let robot2_4_publisher = node.create_publisher::<PointDrive>(TOPIC, None)?;
+    let logger = Logger::new("p9n_interface_2024");
+
+    let mut robot = Robot2_4::new(Box::new(PublisherSink::new(robot2_4_publisher, point_msg)));
+    if let Some(state) = StateFile::from_env() {
+        let policy = StartupPolicy::from_env().unwrap_or_else(|e| {
+            pr_warn!(logger, "{}", e);
+            StartupPolicy::Resume
+        });
+        robot = robot.with_state_file_at(state, policy, clock.now());
+    }
+    add_behaviour(&node, &mut selector, subscriber, Rc::new(RefCell::new(robot)), clock)?;
+    loop {
+        selector.wait()?;
+    }
+}
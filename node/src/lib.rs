@@ -0,0 +1,2 @@
+pub mod mock_driver;
+pub mod msgs;
@@ -0,0 +1,48 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use drobo_interfaces::msg::{MdLibMsg, PointDrive, SdLibMsg};
+use p9n_interface_2024_core::clock::Clock;
+use p9n_interface_2024_core::mock_driver::{MockDriver, MD_TOPIC, POINT_TOPICS, SD_TOPIC};
+use safe_drive::{error::DynError, node::Node, selector::Selector};
+
+use crate::msgs::{md_command, point_command, sd_command};
+
+/// Subscribes `driver` to the MD, SD and point topics, stamping messages
+/// with `clock`.
+pub fn add_mock_subscribers(
+    node: &Arc<Node>,
+    selector: &mut Selector,
+    driver: Rc<RefCell<MockDriver>>,
+    clock: Rc<dyn Clock>,
+) -> Result<(), DynError> {
+    let md = node.create_subscriber::<MdLibMsg>(MD_TOPIC, None)?;
+    let driver_md = driver.clone();
+    let clock_md = clock.clone();
+    selector.add_subscriber(
+        md,
+        Box::new(move |msg| driver_md.borrow_mut().handle_md_at(&md_command(&msg), clock_md.now())),
+    );
+    let sd = node.create_subscriber::<SdLibMsg>(SD_TOPIC, None)?;
+    let driver_sd = driver.clone();
+    let clock_sd = clock.clone();
+    selector.add_subscriber(
+        sd,
+        Box::new(move |msg| driver_sd.borrow_mut().handle_sd_at(&sd_command(&msg), clock_sd.now())),
+    );
+    for topic in POINT_TOPICS {
+        let point = node.create_subscriber::<PointDrive>(topic, None)?;
+        let driver_point = driver.clone();
+        let clock_point = clock.clone();
+        selector.add_subscriber(
+            point,
+            Box::new(move |msg| {
+                driver_point
+                    .borrow_mut()
+                    .handle_point_at(topic, &point_command(&msg), clock_point.now())
+            }),
+        );
+    }
+    Ok(())
+}
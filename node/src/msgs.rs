@@ -0,0 +1,99 @@
+use drobo_interfaces::msg::{MdLibMsg, PointDrive, SdLibMsg};
+use p9n_interface_2024_core::commands::{MdCommand, PointCommand, SdCommand};
+use safe_drive::msg::common_interfaces::std_msgs;
+
+/// The message for one motor command, for `PublisherSink`.
+pub fn md_msg(command: &MdCommand) -> MdLibMsg {
+    let mut msg = MdLibMsg::new().unwrap();
+    msg.address = command.address;
+    msg.mode = command.mode;
+    msg.phase = command.phase;
+    msg.power = command.power;
+    msg
+}
+
+pub fn sd_msg(command: &SdCommand) -> SdLibMsg {
+    let mut msg = SdLibMsg::new().unwrap();
+    msg.address = command.address;
+    msg.port = command.port;
+    msg.power1 = command.power1;
+    msg
+}
+
+pub fn point_msg(command: &PointCommand) -> PointDrive {
+    let mut msg = PointDrive::new().unwrap();
+    msg.md0 = command.md0;
+    msg.md1 = command.md1;
+    msg.md2 = command.md2;
+    msg.md3 = command.md3;
+    msg.md4 = command.md4;
+    msg.md5 = command.md5;
+    msg
+}
+
+/// The air status line robot1 reports.
+pub fn string_msg(text: &str) -> std_msgs::msg::String {
+    let mut msg = std_msgs::msg::String::new().unwrap();
+    msg.data.assign(text);
+    msg
+}
+
+/// The command a received message carries, for the mock driver.
+pub fn md_command(msg: &MdLibMsg) -> MdCommand {
+    MdCommand {
+        address: msg.address,
+        mode: msg.mode,
+        phase: msg.phase,
+        power: msg.power,
+    }
+}
+
+pub fn sd_command(msg: &SdLibMsg) -> SdCommand {
+    SdCommand {
+        address: msg.address,
+        port: msg.port,
+        power1: msg.power1,
+    }
+}
+
+pub fn point_command(msg: &PointDrive) -> PointCommand {
+    PointCommand {
+        md0: msg.md0,
+        md1: msg.md1,
+        md2: msg.md2,
+        md3: msg.md3,
+        md4: msg.md4,
+        md5: msg.md5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let md = MdCommand {
+            address: 0x05,
+            mode: 2,
+            phase: true,
+            power: 300,
+        };
+        assert_eq!(md_command(&md_msg(&md)), md);
+        let sd = SdCommand {
+            address: 0x01,
+            port: 3,
+            power1: 1000,
+        };
+        assert_eq!(sd_command(&sd_msg(&sd)), sd);
+        let point = PointCommand {
+            md0: -1200,
+            md1: 900,
+            md2: -7,
+            md3: 1,
+            md4: 0,
+            md5: 127,
+        };
+        assert_eq!(point_command(&point_msg(&point)), point);
+    }
+}
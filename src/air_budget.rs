@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::commands::SdCommand;
+
+/// Environment variable overriding the tank budget, in shots.
+pub const AIR_BUDGET_ENV: &str = "P9N_AIR_BUDGET";
+
+/// Emitted once when the estimated remaining shots drop to a threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AirWarning {
+    pub remaining: f32,
+    pub threshold: f32,
+}
+
+impl fmt::Display for AirWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.remaining <= 0.0 {
+            write!(f, "air budget used up")
+        } else {
+            write!(f, "air low: about {:.0} shots left", self.remaining)
+        }
+    }
+}
+
+/// Counts solenoid activations (off to on transitions) per address and port
+/// and estimates the remaining air from a tank budget. The budget is measured
+/// in shots; each port costs one shot per activation unless configured
+/// otherwise.
+pub struct AirBudget {
+    capacity: f32,
+    costs: BTreeMap<(u8, u8), f32>,
+    thresholds: Vec<f32>,
+    counts: BTreeMap<(u8, u8), u32>,
+    on: BTreeMap<(u8, u8), bool>,
+    used: f32,
+    warned: usize,
+}
+
+impl AirBudget {
+    pub fn new(capacity: f32) -> AirBudget {
+        AirBudget {
+            capacity,
+            costs: BTreeMap::new(),
+            thresholds: Vec::new(),
+            counts: BTreeMap::new(),
+            on: BTreeMap::new(),
+            used: 0.0,
+            warned: 0,
+        }
+    }
+    /// The budget from `AIR_BUDGET_ENV`, or `capacity` when unset or invalid.
+    pub fn from_env(capacity: f32) -> AirBudget {
+        let capacity = std::env::var(AIR_BUDGET_ENV)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .filter(|value: &f32| value.is_finite() && *value >= 0.0)
+            .unwrap_or(capacity);
+        AirBudget::new(capacity)
+    }
+    /// Sets the air used by one activation of `port`, in shots.
+    pub fn with_cost(mut self, address: u8, port: u8, cost: f32) -> AirBudget {
+        self.costs.insert((address, port), cost);
+        self
+    }
+    /// Warns when the remaining shots reach each of `thresholds`.
+    pub fn with_thresholds(mut self, thresholds: &[f32]) -> AirBudget {
+        self.thresholds = thresholds.to_vec();
+        self.thresholds.sort_by(|a, b| b.total_cmp(a));
+        self
+    }
+    pub fn capacity(&self) -> f32 {
+        self.capacity
+    }
+    pub fn remaining(&self) -> f32 {
+        (self.capacity - self.used).max(0.0)
+    }
+    pub fn count(&self, address: u8, port: u8) -> u32 {
+        *self.counts.get(&(address, port)).unwrap_or(&0)
+    }
+    pub fn total(&self) -> u32 {
+        self.counts.values().sum()
+    }
+    /// Counts `command` if it switches its port on, and returns the warning
+    /// for the lowest threshold it crossed.
+    pub fn record(&mut self, command: &SdCommand) -> Option<AirWarning> {
+        let key = (command.address, command.port);
+        let on = command.is_on();
+        let was_on = self.on.insert(key, on).unwrap_or(false);
+        if !on || was_on {
+            return None;
+        }
+        *self.counts.entry(key).or_insert(0) += 1;
+        self.used += *self.costs.get(&key).unwrap_or(&1.0);
+        let remaining = self.remaining();
+        let mut warning = None;
+        while self.warned < self.thresholds.len() && remaining <= self.thresholds[self.warned] {
+            warning = Some(AirWarning {
+                remaining,
+                threshold: self.thresholds[self.warned],
+            });
+            self.warned += 1;
+        }
+        warning
+    }
+    /// Clears the counters after the tank has been refilled.
+    pub fn refill(&mut self) {
+        self.counts.clear();
+        self.used = 0.0;
+        self.warned = 0;
+    }
+    /// One line for the status topic, e.g.
+    /// `sd0x00.0=3 sd0x00.1=3 remaining=37.0/40`.
+    pub fn status(&self) -> String {
+        let mut status = String::new();
+        for ((address, port), count) in &self.counts {
+            status.push_str(&format!("sd{:#04x}.{}={} ", address, port, count));
+        }
+        status.push_str(&format!("remaining={:.1}/{}", self.remaining(), self.capacity));
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sd(address: u8, port: u8, power1: u16) -> SdCommand {
+        SdCommand { address, port, power1 }
+    }
+
+    #[test]
+    fn counts_rising_edges() {
+        let mut budget = AirBudget::new(10.0).with_cost(0x00, 0, 0.5);
+        budget.record(&sd(0x00, 1, 1000));
+        budget.record(&sd(0x00, 1, 1000));
+        budget.record(&sd(0x00, 1, 0));
+        budget.record(&sd(0x00, 1, 1000));
+        budget.record(&sd(0x00, 0, 1000));
+        assert_eq!(budget.count(0x00, 1), 2);
+        assert_eq!(budget.count(0x00, 0), 1);
+        assert_eq!(budget.total(), 3);
+        assert_eq!(budget.remaining(), 7.5);
+        assert_eq!(budget.status(), "sd0x00.0=1 sd0x00.1=2 remaining=7.5/10");
+    }
+
+    #[test]
+    fn warns_once_per_threshold() {
+        let mut budget = AirBudget::new(4.0).with_thresholds(&[0.0, 2.0]);
+        let mut warnings = Vec::new();
+        for _ in 0..5 {
+            warnings.push(budget.record(&sd(0x00, 1, 1000)).map(|w| w.threshold));
+            budget.record(&sd(0x00, 1, 0));
+        }
+        assert_eq!(warnings, vec![None, Some(2.0), None, Some(0.0), None]);
+        budget.refill();
+        assert_eq!(budget.remaining(), 4.0);
+        assert_eq!(budget.total(), 0);
+    }
+}
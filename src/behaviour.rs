@@ -0,0 +1,105 @@
+#[cfg(feature = "ros")]
+use std::cell::RefCell;
+#[cfg(feature = "ros")]
+use std::rc::Rc;
+#[cfg(feature = "ros")]
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "ros")]
+use safe_drive::{
+    error::DynError, logger::Logger, msg::common_interfaces::sensor_msgs, node::Node, pr_info, selector::Selector,
+    topic::subscriber::Subscriber,
+};
+
+use crate::controller::ControllerState;
+#[cfg(feature = "ros")]
+use crate::clock::Clock;
+#[cfg(feature = "ros")]
+use crate::controller::{add_input_timer, SharedState};
+#[cfg(feature = "ros")]
+use crate::dualsense_hid::{spawn_hidraw, HIDRAW_DEVICE_ENV};
+#[cfg(feature = "ros")]
+use crate::dualsense_sensors::add_sensor_subscribers;
+#[cfg(feature = "ros")]
+use crate::evdev::{spawn_evdev, EVDEV_DEVICE_ENV};
+
+/// Slots of the `[bool; 15]` arrays robots use to detect button edges.
+#[allow(non_snake_case)]
+pub mod DualsenseState {
+    pub const SQUARE: usize = 0;
+    pub const CIRCLE: usize = 1;
+    pub const TRIANGLE: usize = 2;
+    pub const CROSS: usize = 3;
+    pub const L1: usize = 4;
+    pub const L2: usize = 5;
+    pub const R1: usize = 6;
+    pub const R2: usize = 7;
+    pub const D_PAD_UP: usize = 8;
+    pub const D_PAD_DOWN: usize = 9;
+    pub const D_PAD_LEFT: usize = 10;
+    pub const D_PAD_RIGHT: usize = 11;
+    pub const START: usize = 12;
+    pub const SELECT: usize = 13;
+    pub const PS: usize = 14;
+}
+
+/// What a robot does with controller input. Commands go to the sinks the
+/// behaviour was built with, so the same code runs on the robot and in tests.
+pub trait Behaviour {
+    fn on_state_at(&mut self, state: ControllerState, now: Instant);
+    /// How often `tick_at` should run, if the behaviour has timed work.
+    fn tick_period(&self) -> Option<Duration> {
+        None
+    }
+    fn tick_at(&mut self, _now: Instant) {}
+}
+
+/// Feeds `behaviour` from `joy`, or from the evdev device named by
+/// `EVDEV_DEVICE_ENV` or the hidraw device named by `HIDRAW_DEVICE_ENV` when
+/// one is set, plus the optional IMU and touchpad topics, and registers its
+/// tick timer. All inputs update one snapshot, so every call sees the latest
+/// of each. Every call gets its time from `clock`.
+#[cfg(feature = "ros")]
+pub fn add_behaviour<B: Behaviour + 'static>(
+    node: &Arc<Node>,
+    selector: &mut Selector,
+    subscriber: Subscriber<sensor_msgs::msg::Joy>,
+    behaviour: Rc<RefCell<B>>,
+    clock: Rc<dyn Clock>,
+) -> Result<(), DynError> {
+    if let Some(period) = behaviour.borrow().tick_period() {
+        let behaviour = behaviour.clone();
+        let clock = clock.clone();
+        selector.add_wall_timer(
+            "behaviour",
+            period,
+            Box::new(move || behaviour.borrow_mut().tick_at(clock.now())),
+        );
+    }
+    let state = SharedState::new();
+    let on_state: Rc<dyn Fn(ControllerState)> =
+        Rc::new(move |state| behaviour.borrow_mut().on_state_at(state, clock.now()));
+    add_sensor_subscribers(node, selector, state.clone(), on_state.clone())?;
+    if let Some(device) = std::env::var_os(EVDEV_DEVICE_ENV) {
+        pr_info!(Logger::new("p9n_interface_2024"), "reading {}", device.to_string_lossy());
+        add_input_timer(
+            selector,
+            spawn_evdev(device)?,
+            Box::new(move |input| on_state(state.update(|state| state.set_input(&input)))),
+        );
+    } else if let Some(device) = std::env::var_os(HIDRAW_DEVICE_ENV) {
+        pr_info!(Logger::new("p9n_interface_2024"), "reading {}", device.to_string_lossy());
+        add_input_timer(
+            selector,
+            spawn_hidraw(device)?,
+            Box::new(move |input| on_state(state.update(|state| *state = input))),
+        );
+    } else {
+        selector.add_subscriber(
+            subscriber,
+            Box::new(move |msg| on_state(state.update(|state| state.set_joy(&msg)))),
+        );
+    }
+    Ok(())
+}
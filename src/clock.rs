@@ -0,0 +1,176 @@
+use std::cell::Cell;
+#[cfg(feature = "ros")]
+use std::rc::Rc;
+#[cfg(feature = "ros")]
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "ros")]
+use safe_drive::{
+    error::DynError, logger::Logger, msg::interfaces::rosgraph_msgs, node::Node, parameter::Value, pr_info,
+    selector::Selector,
+};
+
+/// The node parameter that switches `ros_clock` to simulator time, as in
+/// other ROS nodes.
+pub const USE_SIM_TIME_PARAM: &str = "use_sim_time";
+/// Environment variable that switches `ros_clock` to simulator time when
+/// the node has no `USE_SIM_TIME_PARAM`.
+pub const USE_SIM_TIME_ENV: &str = "P9N_USE_SIM_TIME";
+/// Where a simulator publishes its time.
+pub const SIM_CLOCK_TOPIC: &str = "/clock";
+
+/// Source of the `now` handed to the `*_at` methods. Everything that waits,
+/// ramps or cools down takes its time from one of these, so tests and
+/// simulators can decide how fast it passes.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The monotonic system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WallClock;
+
+impl Clock for WallClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Time that only moves when told to: by a test, or by `/clock` messages
+/// under simulator time.
+#[derive(Debug)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed: Cell<Duration>,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock {
+            start: Instant::now(),
+            elapsed: Cell::new(Duration::ZERO),
+        }
+    }
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed.get()
+    }
+    /// Jumps to `elapsed`, backwards too.
+    pub fn set_elapsed(&self, elapsed: Duration) {
+        self.elapsed.set(elapsed);
+    }
+    pub fn advance(&self, by: Duration) {
+        self.elapsed.set(self.elapsed.get() + by);
+    }
+    /// Moves to `elapsed` unless that is in the past. Returns whether the
+    /// clock moved.
+    pub fn advance_to(&self, elapsed: Duration) -> bool {
+        if elapsed <= self.elapsed.get() {
+            return false;
+        }
+        self.elapsed.set(elapsed);
+        true
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed.get()
+    }
+}
+
+/// Whether `USE_SIM_TIME_ENV` asks for simulator time.
+pub fn use_sim_time() -> bool {
+    match std::env::var(USE_SIM_TIME_ENV) {
+        Ok(value) => matches!(value.to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"),
+        Err(_) => false,
+    }
+}
+
+/// The node's `USE_SIM_TIME_PARAM`, e.g. from `--ros-args -p
+/// use_sim_time:=true`, or `use_sim_time()` when it is not set. Creates the
+/// node's parameter server, which can only be done once per node.
+#[cfg(feature = "ros")]
+pub fn node_use_sim_time(node: &Arc<Node>) -> Result<bool, DynError> {
+    let server = node.create_parameter_server()?;
+    let params = server.params.read();
+    match params.get_parameter(USE_SIM_TIME_PARAM).map(|param| &param.value) {
+        Some(Value::Bool(value)) => Ok(*value),
+        _ => Ok(use_sim_time()),
+    }
+}
+
+/// A `builtin_interfaces/Time` stamp as time since the simulator started.
+/// Negative stamps count as zero.
+pub fn stamp_elapsed(sec: i32, nanosec: u32) -> Duration {
+    if sec < 0 {
+        return Duration::ZERO;
+    }
+    Duration::new(sec as u64, nanosec)
+}
+
+/// The wall clock, or with `node_use_sim_time` a clock that follows
+/// `SIM_CLOCK_TOPIC` and stands still until the simulator publishes. A sim
+/// clock that jumps back (a simulator reset) is ignored until it catches up.
+#[cfg(feature = "ros")]
+pub fn ros_clock(node: &Arc<Node>, selector: &mut Selector) -> Result<Rc<dyn Clock>, DynError> {
+    if !node_use_sim_time(node)? {
+        return Ok(Rc::new(WallClock));
+    }
+    let logger = Logger::new("p9n_interface_2024");
+    pr_info!(logger, "using simulator time from {}", SIM_CLOCK_TOPIC);
+    let clock = Rc::new(ManualClock::new());
+    let subscriber = node.create_subscriber::<rosgraph_msgs::msg::Clock>(SIM_CLOCK_TOPIC, None)?;
+    let clock_sub = clock.clone();
+    selector.add_subscriber(
+        subscriber,
+        Box::new(move |msg| {
+            clock_sub.advance_to(stamp_elapsed(msg.clock.sec, msg.clock.nanosec));
+        }),
+    );
+    Ok(clock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manual_clock_moves_only_when_told() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+        clock.advance(Duration::from_millis(20));
+        assert_eq!(clock.now() - start, Duration::from_millis(20));
+        assert!(!clock.advance_to(Duration::from_millis(10)));
+        assert_eq!(clock.elapsed(), Duration::from_millis(20));
+        assert!(clock.advance_to(Duration::from_millis(50)));
+        clock.set_elapsed(Duration::ZERO);
+        assert_eq!(clock.now(), start);
+    }
+
+    #[test]
+    fn sim_stamps() {
+        assert_eq!(stamp_elapsed(2, 500_000_000), Duration::from_millis(2500));
+        assert_eq!(stamp_elapsed(-1, 0), Duration::ZERO);
+    }
+
+    #[test]
+    fn drives_time_based_logic() {
+        use crate::rate_limit::RateLimit;
+
+        let clock = ManualClock::new();
+        let mut limit = RateLimit::new(Duration::from_millis(400));
+        assert!(limit.try_fire_at(clock.now()).is_ok());
+        clock.advance(Duration::from_millis(399));
+        assert!(limit.try_fire_at(clock.now()).is_err());
+        clock.advance(Duration::from_millis(1));
+        assert!(limit.try_fire_at(clock.now()).is_ok());
+    }
+}
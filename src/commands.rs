@@ -0,0 +1,81 @@
+use std::fmt;
+
+/// One `MdLibMsg` as plain data: a motor on an MD driver board. Everything
+/// in this crate sends these records; the node turns them into
+/// drobo_interfaces messages at the publisher, so the logic builds and tests
+/// without the ROS message build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MdCommand {
+    pub address: u8,
+    pub mode: u8,
+    /// Set for negative power.
+    pub phase: bool,
+    pub power: u16,
+}
+
+impl fmt::Display for MdCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "address=0x{:02x} mode={} phase={} power={}",
+            self.address, self.mode, self.phase, self.power
+        )
+    }
+}
+
+/// One `SdLibMsg`: a port on an SD driver board, on for any non-zero
+/// `power1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SdCommand {
+    pub address: u8,
+    pub port: u8,
+    pub power1: u16,
+}
+
+impl SdCommand {
+    pub fn is_on(&self) -> bool {
+        self.power1 != 0
+    }
+}
+
+impl fmt::Display for SdCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "address=0x{:02x} port={} power1={}", self.address, self.port, self.power1)
+    }
+}
+
+/// One `PointDrive`: md0 and md1 are `i16`, the rest `i8`, as in the
+/// message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PointCommand {
+    pub md0: i16,
+    pub md1: i16,
+    pub md2: i8,
+    pub md3: i8,
+    pub md4: i8,
+    pub md5: i8,
+}
+
+impl PointCommand {
+    /// The fields as `[md0, .., md5]`.
+    pub fn values(&self) -> [i16; 6] {
+        [
+            self.md0,
+            self.md1,
+            self.md2 as i16,
+            self.md3 as i16,
+            self.md4 as i16,
+            self.md5 as i16,
+        ]
+    }
+}
+
+impl fmt::Display for PointCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "md0={} md1={} md2={} md3={} md4={} md5={}",
+            self.md0, self.md1, self.md2, self.md3, self.md4, self.md5
+        )
+    }
+}
@@ -0,0 +1,287 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+#[cfg(feature = "ros")]
+use safe_drive::{msg::common_interfaces::sensor_msgs, selector::Selector};
+
+use crate::dualsense_sensors::{ImuReading, TouchPad};
+use crate::ps5_dualsense::AXES_DUALSENSE;
+
+pub const BUTTON_COUNT: usize = 13;
+pub const AXIS_COUNT: usize = 8;
+pub const INPUT_POLL_PERIOD: Duration = Duration::from_millis(5);
+
+/// One controller snapshot, independent of where it came from. `buttons`
+/// and `axes` are indexed by `BUTTONS_DUALSENSE` and `AXES_DUALSENSE` and
+/// use the joy node's sign conventions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControllerState {
+    pub buttons: [bool; BUTTON_COUNT],
+    pub axes: [f32; AXIS_COUNT],
+    pub imu: Option<ImuReading>,
+    pub touchpad: TouchPad,
+}
+
+impl ControllerState {
+    pub fn button(&self, index: usize) -> bool {
+        self.buttons.get(index).copied().unwrap_or(false)
+    }
+    pub fn axis(&self, index: usize) -> f32 {
+        self.axes.get(index).copied().unwrap_or(0.0)
+    }
+    /// Copies buttons and axes from a Joy message. Entries the message does
+    /// not have keep their released values.
+    #[cfg(feature = "ros")]
+    pub fn from_joy(msg: &sensor_msgs::msg::Joy) -> ControllerState {
+        let mut state = ControllerState::default();
+        state.set_joy(msg);
+        state
+    }
+    /// Like `from_joy`, but keeps the IMU and touchpad readings.
+    #[cfg(feature = "ros")]
+    pub fn set_joy(&mut self, msg: &sensor_msgs::msg::Joy) {
+        self.set_joy_parts(msg.buttons.as_slice(), msg.axes.as_slice());
+    }
+    /// Takes the IMU reading from an `sensor_msgs/Imu` message.
+    #[cfg(feature = "ros")]
+    pub fn set_imu(&mut self, msg: &sensor_msgs::msg::Imu) {
+        self.imu = Some(ImuReading::from_msg(msg));
+    }
+    /// Takes the touch points from a touch message laid out as in
+    /// `TOUCH_DUALSENSE`.
+    #[cfg(feature = "ros")]
+    pub fn set_touch(&mut self, msg: &sensor_msgs::msg::Joy) {
+        self.touchpad = TouchPad::from_joy(msg);
+    }
+    /// Takes buttons and axes from `input`, keeping the IMU and touchpad
+    /// readings, for sources such as evdev that only see buttons and axes.
+    pub fn set_input(&mut self, input: &ControllerState) {
+        self.buttons = input.buttons;
+        self.axes = input.axes;
+    }
+    /// `from_joy` on the raw arrays of a Joy message.
+    pub fn from_joy_parts(buttons: &[i32], axes: &[f32]) -> ControllerState {
+        let mut state = ControllerState::default();
+        state.set_joy_parts(buttons, axes);
+        state
+    }
+    /// Missing and non-finite axes read as released; the rest are clamped to
+    /// -1.0..=1.0. Extra entries are ignored.
+    pub fn set_joy_parts(&mut self, buttons: &[i32], axes: &[f32]) {
+        let released = ControllerState::default();
+        for (i, button) in self.buttons.iter_mut().enumerate() {
+            *button = buttons.get(i).is_some_and(|value| *value == 1);
+        }
+        for (i, axis) in self.axes.iter_mut().enumerate() {
+            *axis = match axes.get(i) {
+                Some(value) if value.is_finite() => value.clamp(-1.0, 1.0),
+                _ => released.axes[i],
+            };
+        }
+    }
+    #[cfg(feature = "ros")]
+    pub fn to_joy(&self) -> sensor_msgs::msg::Joy {
+        let mut msg = sensor_msgs::msg::Joy::new().unwrap();
+        msg.axes = safe_drive::msg::F32Seq::new(AXIS_COUNT).unwrap();
+        msg.axes.as_slice_mut().copy_from_slice(&self.axes);
+        msg.buttons = safe_drive::msg::I32Seq::new(BUTTON_COUNT).unwrap();
+        for (button, pressed) in msg.buttons.as_slice_mut().iter_mut().zip(self.buttons) {
+            *button = pressed as i32;
+        }
+        msg
+    }
+}
+
+impl Default for ControllerState {
+    /// Nothing pressed, sticks centred, triggers released.
+    fn default() -> Self {
+        let mut axes = [0.0; AXIS_COUNT];
+        axes[AXES_DUALSENSE::L2] = 1.0;
+        axes[AXES_DUALSENSE::R2] = 1.0;
+        ControllerState {
+            buttons: [false; BUTTON_COUNT],
+            axes,
+            imu: None,
+            touchpad: TouchPad::default(),
+        }
+    }
+}
+
+/// The snapshot every input callback of one controller updates in place, so
+/// a Joy message keeps the latest IMU and touchpad readings and the other way
+/// round.
+#[derive(Debug, Clone, Default)]
+pub struct SharedState(Rc<RefCell<ControllerState>>);
+
+impl SharedState {
+    pub fn new() -> SharedState {
+        SharedState::default()
+    }
+    pub fn get(&self) -> ControllerState {
+        self.0.borrow().clone()
+    }
+    /// Applies `update` and returns the resulting snapshot.
+    pub fn update(&self, update: impl FnOnce(&mut ControllerState)) -> ControllerState {
+        let mut state = self.0.borrow_mut();
+        update(&mut state);
+        state.clone()
+    }
+}
+
+/// Anything that produces controller snapshots: a Joy subscription, an
+/// evdev thread, a recording or a test script.
+pub trait InputSource {
+    /// The next pending snapshot, without blocking.
+    fn poll(&mut self) -> Option<ControllerState>;
+}
+
+impl InputSource for Receiver<ControllerState> {
+    fn poll(&mut self) -> Option<ControllerState> {
+        self.try_recv().ok()
+    }
+}
+
+impl InputSource for VecDeque<ControllerState> {
+    fn poll(&mut self) -> Option<ControllerState> {
+        self.pop_front()
+    }
+}
+
+impl<S: InputSource> InputSource for Rc<RefCell<S>> {
+    fn poll(&mut self) -> Option<ControllerState> {
+        self.borrow_mut().poll()
+    }
+}
+
+/// Button edges between consecutive snapshots.
+#[derive(Debug, Clone, Default)]
+pub struct Edges {
+    previous: [bool; BUTTON_COUNT],
+    current: [bool; BUTTON_COUNT],
+}
+
+impl Edges {
+    pub fn new() -> Edges {
+        Edges::default()
+    }
+    pub fn update(&mut self, state: &ControllerState) {
+        self.previous = self.current;
+        self.current = state.buttons;
+    }
+    pub fn held(&self, index: usize) -> bool {
+        self.current[index]
+    }
+    pub fn pressed(&self, index: usize) -> bool {
+        self.current[index] && !self.previous[index]
+    }
+    pub fn released(&self, index: usize) -> bool {
+        !self.current[index] && self.previous[index]
+    }
+}
+
+/// Drains `source` from a wall timer and hands every snapshot to `on_state`.
+#[cfg(feature = "ros")]
+pub fn add_input_timer(
+    selector: &mut Selector,
+    mut source: impl InputSource + 'static,
+    mut on_state: Box<dyn FnMut(ControllerState)>,
+) -> u64 {
+    selector.add_wall_timer(
+        "input",
+        INPUT_POLL_PERIOD,
+        Box::new(move || {
+            while let Some(state) = source.poll() {
+                on_state(state);
+            }
+        }),
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::joint::trigger_command;
+    use crate::p9n_interface::PlaystationInterface;
+    use crate::ps5_dualsense::BUTTONS_DUALSENSE;
+
+    fn with_cross(pressed: bool) -> ControllerState {
+        let mut state = ControllerState::default();
+        state.buttons[BUTTONS_DUALSENSE::CROSS] = pressed;
+        state
+    }
+
+    #[test]
+    fn edges_from_scripted_source() {
+        let mut source: VecDeque<ControllerState> =
+            [with_cross(true), with_cross(true), with_cross(false)].into_iter().collect();
+        let mut edges = Edges::new();
+        let mut seen = Vec::new();
+        while let Some(state) = source.poll() {
+            edges.update(&state);
+            let cross = BUTTONS_DUALSENSE::CROSS;
+            seen.push((edges.pressed(cross), edges.held(cross), edges.released(cross)));
+        }
+        assert_eq!(seen, vec![(true, true, false), (false, true, false), (false, false, true)]);
+    }
+
+    #[cfg(feature = "ros")]
+    #[test]
+    fn short_joy_keeps_released_values() {
+        let mut msg = sensor_msgs::msg::Joy::new().unwrap();
+        msg.buttons = safe_drive::msg::I32Seq::new(1).unwrap();
+        msg.buttons.as_slice_mut()[0] = 1;
+        let state = ControllerState::from_joy(&msg);
+        assert!(state.button(BUTTONS_DUALSENSE::CROSS));
+        assert_eq!(state.axis(AXES_DUALSENSE::R2), 1.0);
+        assert_eq!(ControllerState::from_joy(&state.to_joy()), state);
+    }
+
+    #[test]
+    fn sensors_survive_joy_updates() {
+        let imu = ImuReading { linear_acceleration: [0.0, 0.0, 9.8], ..Default::default() };
+        let shared = SharedState::new();
+        shared.update(|state| state.imu = Some(imu));
+        let state = shared.update(|state| state.set_joy_parts(&[1], &[]));
+        assert!(state.button(BUTTONS_DUALSENSE::CROSS));
+        assert_eq!(state.imu, Some(imu));
+        let state = shared.update(|state| state.set_input(&ControllerState::default()));
+        assert!(!state.button(BUTTONS_DUALSENSE::CROSS));
+        assert_eq!(state.imu, Some(imu));
+        assert_eq!(shared.get(), state);
+    }
+
+    /// Joy-shaped input of any length: buttons mostly 0/1 with some garbage,
+    /// axes including NaN and infinities.
+    pub(crate) fn joy_parts() -> impl Strategy<Value = (Vec<i32>, Vec<f32>)> {
+        (
+            prop::collection::vec(prop_oneof![Just(0), Just(1), any::<i32>()], 0..20),
+            prop::collection::vec(prop::num::f32::ANY, 0..12),
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn any_joy_shape_gives_a_sane_state((buttons, axes) in joy_parts()) {
+            let state = ControllerState::from_joy_parts(&buttons, &axes);
+            for (i, axis) in state.axes.iter().enumerate() {
+                prop_assert!((-1.0..=1.0).contains(axis), "axis {} = {}", i, axis);
+            }
+            for (i, pressed) in state.buttons.iter().enumerate() {
+                prop_assert_eq!(*pressed, buttons.get(i) == Some(&1));
+            }
+            let p9n = PlaystationInterface::from_state(state.clone());
+            prop_assert!(!(p9n.pressed_dpad_left() && p9n.pressed_dpad_right()));
+            prop_assert!(!(p9n.pressed_dpad_up() && p9n.pressed_dpad_down()));
+            prop_assert!((0.0..=1.0).contains(&trigger_command(p9n.pressed_l2_analog())));
+            prop_assert!((0.0..=1.0).contains(&trigger_command(p9n.pressed_r2_analog())));
+
+            let buttons: Vec<i32> = state.buttons.iter().map(|pressed| *pressed as i32).collect();
+            prop_assert_eq!(ControllerState::from_joy_parts(&buttons, &state.axes), state);
+        }
+    }
+}
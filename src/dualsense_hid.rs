@@ -0,0 +1,429 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::controller::ControllerState;
+use crate::dualsense_sensors::{ImuReading, TouchPad, TouchPoint};
+use crate::feedback::Rgb;
+use crate::log::{pr_warn, Logger};
+use crate::ps5_dualsense::{AXES_DUALSENSE, BUTTONS_DUALSENSE};
+
+/// Environment variable naming the `/dev/hidraw*` device to read instead of
+/// subscribing to `joy`. Unlike evdev, the raw reports also carry the IMU
+/// and touchpad.
+pub const HIDRAW_DEVICE_ENV: &str = "P9N_HIDRAW_DEVICE";
+
+pub const USB_INPUT_REPORT_ID: u8 = 0x01;
+pub const USB_INPUT_REPORT_SIZE: usize = 64;
+pub const BT_INPUT_REPORT_ID: u8 = 0x31;
+pub const BT_INPUT_REPORT_SIZE: usize = 78;
+pub const USB_OUTPUT_REPORT_ID: u8 = 0x02;
+pub const USB_OUTPUT_REPORT_SIZE: usize = 63;
+pub const BT_OUTPUT_REPORT_ID: u8 = 0x31;
+pub const BT_OUTPUT_REPORT_SIZE: usize = 78;
+
+/// Bluetooth reports are checksummed with a one byte prefix.
+const BT_INPUT_CRC_SEED: u8 = 0xa1;
+const BT_OUTPUT_CRC_SEED: u8 = 0xa2;
+
+const GYRO_RES_PER_DEG_S: f64 = 1024.0;
+const ACCEL_RES_PER_G: f64 = 8192.0;
+const STANDARD_GRAVITY: f64 = 9.80665;
+const TOUCHPAD_WIDTH: f32 = 1920.0;
+const TOUCHPAD_HEIGHT: f32 = 1080.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Usb,
+    Bluetooth,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HidError {
+    UnknownReport(u8),
+    TooShort { len: usize, expected: usize },
+    BadCrc { expected: u32, actual: u32 },
+}
+
+impl fmt::Display for HidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HidError::UnknownReport(id) => write!(f, "unknown report id {:#04x}", id),
+            HidError::TooShort { len, expected } => write!(f, "report is {} bytes, expected {}", len, expected),
+            HidError::BadCrc { expected, actual } => {
+                write!(f, "report crc {:#010x} does not match {:#010x}", actual, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HidError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChargeStatus {
+    #[default]
+    Discharging,
+    Charging,
+    Full,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Battery {
+    /// Estimated charge in percent.
+    pub percent: u8,
+    pub status: ChargeStatus,
+}
+
+/// Everything in one input report: the controller snapshot plus what only
+/// the HID report carries.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HidState {
+    pub controller: ControllerState,
+    pub touchpad_click: bool,
+    pub mute: bool,
+    pub battery: Battery,
+}
+
+/// Decodes a USB (0x01) or Bluetooth (0x31) input report. Bluetooth reports
+/// have their CRC checked.
+pub fn parse_input_report(report: &[u8]) -> Result<(Transport, HidState), HidError> {
+    let (transport, data) = match report.first() {
+        Some(&USB_INPUT_REPORT_ID) => {
+            check_len(report, USB_INPUT_REPORT_SIZE)?;
+            (Transport::Usb, &report[1..])
+        }
+        Some(&BT_INPUT_REPORT_ID) => {
+            check_len(report, BT_INPUT_REPORT_SIZE)?;
+            check_crc(&report[..BT_INPUT_REPORT_SIZE], BT_INPUT_CRC_SEED)?;
+            (Transport::Bluetooth, &report[2..])
+        }
+        Some(&id) => return Err(HidError::UnknownReport(id)),
+        None => return Err(HidError::TooShort { len: 0, expected: USB_INPUT_REPORT_SIZE }),
+    };
+    Ok((transport, parse_common(data)))
+}
+
+/// Rumble, lightbar and player LED settings for an output report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputState {
+    pub rumble_left: u8,
+    pub rumble_right: u8,
+    pub lightbar: Rgb,
+    /// Bits 0 to 4 light the five player LEDs from left to right.
+    pub player_leds: u8,
+}
+
+/// Builds an output report. `sequence` only matters over Bluetooth, where
+/// it should increase by one per report.
+pub fn output_report(state: &OutputState, transport: Transport, sequence: u8) -> Vec<u8> {
+    let (mut report, common) = match transport {
+        Transport::Usb => {
+            let mut report = vec![0; USB_OUTPUT_REPORT_SIZE];
+            report[0] = USB_OUTPUT_REPORT_ID;
+            (report, 1)
+        }
+        Transport::Bluetooth => {
+            let mut report = vec![0; BT_OUTPUT_REPORT_SIZE];
+            report[0] = BT_OUTPUT_REPORT_ID;
+            report[1] = (sequence & 0x0f) << 4;
+            report[2] = 0x10;
+            (report, 3)
+        }
+    };
+    let fields = &mut report[common..];
+    // Compatible vibration and haptics select.
+    fields[0] = 0x03;
+    // Lightbar and player indicator control.
+    fields[1] = 0x04 | 0x10;
+    fields[2] = state.rumble_right;
+    fields[3] = state.rumble_left;
+    fields[43] = state.player_leds & 0x1f;
+    fields[44] = channel(state.lightbar.r);
+    fields[45] = channel(state.lightbar.g);
+    fields[46] = channel(state.lightbar.b);
+    if transport == Transport::Bluetooth {
+        let crc = crc32(BT_OUTPUT_CRC_SEED, &report[..BT_OUTPUT_REPORT_SIZE - 4]);
+        report[BT_OUTPUT_REPORT_SIZE - 4..].copy_from_slice(&crc.to_le_bytes());
+    }
+    report
+}
+
+/// Sends a snapshot per input report until the stream ends or fails, then
+/// the released state so nothing stays held. Each `read` returns one report;
+/// reports that do not parse are skipped. Returns why it stopped, or `None`
+/// if the receiver went away.
+fn forward<R: Read>(mut reader: R, sender: &Sender<ControllerState>) -> Option<String> {
+    let mut report = [0; BT_INPUT_REPORT_SIZE];
+    let reason = loop {
+        match reader.read(&mut report) {
+            Ok(0) => break "device closed".to_string(),
+            Ok(len) => {
+                if let Ok((_, state)) = parse_input_report(&report[..len]) {
+                    if sender.send(state.controller).is_err() {
+                        return None;
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => break e.to_string(),
+        }
+    };
+    let _ = sender.send(ControllerState::default());
+    Some(reason)
+}
+
+/// Reads input reports from `path` on its own thread and sends a snapshot,
+/// IMU and touchpad included, per report. The receiver is an `InputSource`.
+/// When the device fails or is unplugged the error is logged and a released
+/// snapshot is sent before the thread exits.
+pub fn spawn_hidraw(path: impl Into<PathBuf>) -> io::Result<Receiver<ControllerState>> {
+    let path = path.into();
+    let reader = File::open(&path)?;
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        if let Some(reason) = forward(reader, &sender) {
+            pr_warn!(Logger::new("p9n_interface_2024"), "{}: {}", path.display(), reason);
+        }
+    });
+    Ok(receiver)
+}
+
+/// CRC-32 (as in zlib) of `seed` followed by `data`.
+pub fn crc32(seed: u8, data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in std::iter::once(&seed).chain(data) {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn check_len(report: &[u8], expected: usize) -> Result<(), HidError> {
+    if report.len() < expected {
+        return Err(HidError::TooShort { len: report.len(), expected });
+    }
+    Ok(())
+}
+
+fn check_crc(report: &[u8], seed: u8) -> Result<(), HidError> {
+    let (body, tail) = report.split_at(report.len() - 4);
+    let expected = u32::from_le_bytes([tail[0], tail[1], tail[2], tail[3]]);
+    let actual = crc32(seed, body);
+    if actual != expected {
+        return Err(HidError::BadCrc { expected, actual });
+    }
+    Ok(())
+}
+
+fn channel(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Sticks are 0 (left/up) to 255; the joy node reports left and up as +1.
+fn stick(raw: u8) -> f32 {
+    ((128.0 - raw as f32) / 127.0).clamp(-1.0, 1.0)
+}
+
+/// Triggers are 0 (released) to 255; the joy node reports 1.0 to -1.0.
+fn trigger(raw: u8) -> f32 {
+    1.0 - raw as f32 / 127.5
+}
+
+fn le_i16(data: &[u8], offset: usize) -> f64 {
+    i16::from_le_bytes([data[offset], data[offset + 1]]) as f64
+}
+
+fn touch_point(bytes: &[u8]) -> Option<TouchPoint> {
+    // The top bit of the contact byte is set while the finger is lifted.
+    if bytes[0] & 0x80 != 0 {
+        return None;
+    }
+    let x = bytes[1] as u16 | ((bytes[2] as u16 & 0x0f) << 8);
+    let y = (bytes[2] as u16 >> 4) | ((bytes[3] as u16) << 4);
+    Some(TouchPoint {
+        x: x as f32 / TOUCHPAD_WIDTH,
+        y: y as f32 / TOUCHPAD_HEIGHT,
+    })
+}
+
+/// Decodes the report body shared by USB and Bluetooth, starting at the left
+/// stick X byte.
+fn parse_common(data: &[u8]) -> HidState {
+    let mut state = HidState::default();
+
+    state.controller.axes[AXES_DUALSENSE::STICK_LX] = stick(data[0]);
+    state.controller.axes[AXES_DUALSENSE::STICK_LY] = stick(data[1]);
+    state.controller.axes[AXES_DUALSENSE::STICK_RX] = stick(data[2]);
+    state.controller.axes[AXES_DUALSENSE::STICK_RY] = stick(data[3]);
+    state.controller.axes[AXES_DUALSENSE::L2] = trigger(data[4]);
+    state.controller.axes[AXES_DUALSENSE::R2] = trigger(data[5]);
+
+    let (buttons0, buttons1, buttons2) = (data[7], data[8], data[9]);
+    // Hat switch: 0 is up, counting clockwise in eighths, 8 is released.
+    let (dpad_x, dpad_y) = match buttons0 & 0x0f {
+        0 => (0.0, 1.0),
+        1 => (-1.0, 1.0),
+        2 => (-1.0, 0.0),
+        3 => (-1.0, -1.0),
+        4 => (0.0, -1.0),
+        5 => (1.0, -1.0),
+        6 => (1.0, 0.0),
+        7 => (1.0, 1.0),
+        _ => (0.0, 0.0),
+    };
+    state.controller.axes[AXES_DUALSENSE::DPAD_X] = dpad_x;
+    state.controller.axes[AXES_DUALSENSE::DPAD_Y] = dpad_y;
+
+    let bit = |byte: u8, bit: u8| byte & (1 << bit) != 0;
+    state.controller.buttons[BUTTONS_DUALSENSE::SQUARE] = bit(buttons0, 4);
+    state.controller.buttons[BUTTONS_DUALSENSE::CROSS] = bit(buttons0, 5);
+    state.controller.buttons[BUTTONS_DUALSENSE::CIRCLE] = bit(buttons0, 6);
+    state.controller.buttons[BUTTONS_DUALSENSE::TRIANGLE] = bit(buttons0, 7);
+    state.controller.buttons[BUTTONS_DUALSENSE::L1] = bit(buttons1, 0);
+    state.controller.buttons[BUTTONS_DUALSENSE::R1] = bit(buttons1, 1);
+    state.controller.buttons[BUTTONS_DUALSENSE::L2] = bit(buttons1, 2);
+    state.controller.buttons[BUTTONS_DUALSENSE::R2] = bit(buttons1, 3);
+    state.controller.buttons[BUTTONS_DUALSENSE::SELECT] = bit(buttons1, 4);
+    state.controller.buttons[BUTTONS_DUALSENSE::START] = bit(buttons1, 5);
+    state.controller.buttons[BUTTONS_DUALSENSE::STICK_L_PUSH] = bit(buttons1, 6);
+    state.controller.buttons[BUTTONS_DUALSENSE::STICK_R_PUSH] = bit(buttons1, 7);
+    state.controller.buttons[BUTTONS_DUALSENSE::PS] = bit(buttons2, 0);
+    state.touchpad_click = bit(buttons2, 1);
+    state.mute = bit(buttons2, 2);
+
+    let gyro = |axis: usize| (le_i16(data, 15 + 2 * axis) / GYRO_RES_PER_DEG_S).to_radians();
+    let accel = |axis: usize| le_i16(data, 21 + 2 * axis) / ACCEL_RES_PER_G * STANDARD_GRAVITY;
+    state.controller.imu = Some(ImuReading {
+        angular_velocity: [gyro(0), gyro(1), gyro(2)],
+        linear_acceleration: [accel(0), accel(1), accel(2)],
+    });
+
+    state.controller.touchpad = TouchPad {
+        points: [touch_point(&data[32..36]), touch_point(&data[36..40])],
+    };
+
+    let status = data[52];
+    state.battery = Battery {
+        percent: ((status & 0x0f) * 10 + 5).min(100),
+        status: match status >> 4 {
+            0x0 => ChargeStatus::Discharging,
+            0x1 => ChargeStatus::Charging,
+            0x2 => ChargeStatus::Full,
+            _ => ChargeStatus::Error,
+        },
+    };
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// USB report: left stick full left, right stick centred, R2 fully
+    /// pressed, d-pad right, cross + L1 + PS held, flat on the table, one
+    /// finger at (960, 540), battery 55% charging.
+    const USB_REPORT: [u8; USB_INPUT_REPORT_SIZE] = [
+        0x01, 0x00, 0x80, 0x80, 0x80, 0x00, 0xff, 0x2a, 0x22, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x05, 0xc0, 0xc3, 0x21, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    fn bt_report() -> Vec<u8> {
+        let mut report = vec![0; BT_INPUT_REPORT_SIZE];
+        report[0] = BT_INPUT_REPORT_ID;
+        report[2..USB_INPUT_REPORT_SIZE + 1].copy_from_slice(&USB_REPORT[1..]);
+        let crc = crc32(BT_INPUT_CRC_SEED, &report[..BT_INPUT_REPORT_SIZE - 4]);
+        report[BT_INPUT_REPORT_SIZE - 4..].copy_from_slice(&crc.to_le_bytes());
+        report
+    }
+
+    #[test]
+    fn crc_check_value() {
+        // Standard CRC-32 check value of "123456789", with '1' as the seed.
+        assert_eq!(crc32(b'1', b"23456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn parses_usb_report() {
+        let (transport, state) = parse_input_report(&USB_REPORT).unwrap();
+        assert_eq!(transport, Transport::Usb);
+        assert_eq!(state.controller.axes[AXES_DUALSENSE::STICK_LX], 1.0);
+        assert_eq!(state.controller.axes[AXES_DUALSENSE::STICK_RY], 0.0);
+        assert_eq!(state.controller.axes[AXES_DUALSENSE::L2], 1.0);
+        assert_eq!(state.controller.axes[AXES_DUALSENSE::R2], -1.0);
+        assert_eq!(state.controller.axes[AXES_DUALSENSE::DPAD_X], -1.0);
+        let pressed: Vec<usize> = (0..13).filter(|&i| state.controller.buttons[i]).collect();
+        assert_eq!(pressed, vec![BUTTONS_DUALSENSE::CROSS, BUTTONS_DUALSENSE::L1, BUTTONS_DUALSENSE::PS]);
+        assert!((state.controller.imu.unwrap().linear_acceleration[2] - STANDARD_GRAVITY).abs() < 1e-9);
+        assert_eq!(state.controller.touchpad.points, [Some(TouchPoint { x: 0.5, y: 0.5 }), None]);
+        assert_eq!(state.battery, Battery { percent: 55, status: ChargeStatus::Charging });
+    }
+
+    #[test]
+    fn bluetooth_matches_usb_and_checks_crc() {
+        let mut report = bt_report();
+        let (transport, state) = parse_input_report(&report).unwrap();
+        assert_eq!(transport, Transport::Bluetooth);
+        assert_eq!(state, parse_input_report(&USB_REPORT).unwrap().1);
+        report[10] ^= 0x01;
+        assert!(matches!(parse_input_report(&report), Err(HidError::BadCrc { .. })));
+        assert_eq!(
+            parse_input_report(&report[..10]),
+            Err(HidError::TooShort { len: 10, expected: BT_INPUT_REPORT_SIZE })
+        );
+        assert_eq!(parse_input_report(&[0x05]), Err(HidError::UnknownReport(0x05)));
+    }
+
+    #[test]
+    fn builds_output_reports() {
+        let state = OutputState {
+            rumble_left: 0x40,
+            rumble_right: 0x80,
+            lightbar: Rgb::new(1.0, 0.0, 0.5),
+            player_leds: 0x04,
+        };
+        let usb = output_report(&state, Transport::Usb, 0);
+        assert_eq!(usb.len(), USB_OUTPUT_REPORT_SIZE);
+        assert_eq!(&usb[..5], &[0x02, 0x03, 0x14, 0x80, 0x40]);
+        assert_eq!(&usb[44..48], &[0x04, 0xff, 0x00, 0x80]);
+
+        let bt = output_report(&state, Transport::Bluetooth, 3);
+        assert_eq!(bt.len(), BT_OUTPUT_REPORT_SIZE);
+        assert_eq!(&bt[..3], &[0x31, 0x30, 0x10]);
+        assert_eq!(&bt[3..50], &usb[1..48]);
+        assert!(check_crc(&bt, BT_OUTPUT_CRC_SEED).is_ok());
+    }
+
+    /// A hidraw node: one report per read, then the device goes away.
+    struct Reports(Vec<Vec<u8>>);
+
+    impl Read for Reports {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::other("no such device"));
+            }
+            let report = self.0.remove(0);
+            buf[..report.len()].copy_from_slice(&report);
+            Ok(report.len())
+        }
+    }
+
+    #[test]
+    fn forwards_reports_and_releases_when_unplugged() {
+        let (sender, receiver) = mpsc::channel();
+        let reports = Reports(vec![USB_REPORT.to_vec(), vec![0x05, 0x00], bt_report()]);
+        assert_eq!(forward(reports, &sender).as_deref(), Some("no such device"));
+        let frames: Vec<ControllerState> = receiver.try_iter().collect();
+        assert_eq!(frames.len(), 3);
+        assert!(frames[0].buttons[BUTTONS_DUALSENSE::CROSS]);
+        assert!(frames[0].imu.is_some());
+        assert_eq!(frames[1], frames[0]);
+        assert_eq!(frames[2], ControllerState::default());
+    }
+}
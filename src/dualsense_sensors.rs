@@ -0,0 +1,139 @@
+#[cfg(feature = "ros")]
+use std::rc::Rc;
+#[cfg(feature = "ros")]
+use std::sync::Arc;
+
+#[cfg(feature = "ros")]
+use safe_drive::{error::DynError, msg::common_interfaces::sensor_msgs, node::Node, selector::Selector};
+
+#[cfg(feature = "ros")]
+use crate::controller::{ControllerState, SharedState};
+#[cfg(feature = "ros")]
+use crate::ps5_dualsense::TOUCH_DUALSENSE;
+
+/// Environment variables naming the IMU and touchpad topics. Each
+/// subscription is only made when its variable is set.
+pub const IMU_TOPIC_ENV: &str = "P9N_IMU_TOPIC";
+pub const TOUCH_TOPIC_ENV: &str = "P9N_TOUCH_TOPIC";
+
+/// One `sensor_msgs/Imu` sample: angular velocity in rad/s and linear
+/// acceleration in m/s², both in the controller frame.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ImuReading {
+    pub angular_velocity: [f64; 3],
+    pub linear_acceleration: [f64; 3],
+}
+
+impl ImuReading {
+    #[cfg(feature = "ros")]
+    pub fn from_msg(msg: &sensor_msgs::msg::Imu) -> ImuReading {
+        let w = &msg.angular_velocity;
+        let a = &msg.linear_acceleration;
+        ImuReading {
+            angular_velocity: [w.x, w.y, w.z],
+            linear_acceleration: [a.x, a.y, a.z],
+        }
+    }
+    /// Roll and pitch in radians, estimated from the direction of gravity.
+    /// Only meaningful while the controller is not being shaken.
+    pub fn tilt(&self) -> (f64, f64) {
+        let [x, y, z] = self.linear_acceleration;
+        let roll = y.atan2(z);
+        let pitch = (-x).atan2((y * y + z * z).sqrt());
+        (roll, pitch)
+    }
+}
+
+/// A finger on the touchpad, with coordinates from 0.0 to 1.0 starting at
+/// the top left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Up to two fingers on the touchpad.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TouchPad {
+    pub points: [Option<TouchPoint>; 2],
+}
+
+impl TouchPad {
+    /// Reads a touch message laid out as in `TOUCH_DUALSENSE`. Missing
+    /// entries count as no touch.
+    #[cfg(feature = "ros")]
+    pub fn from_joy(msg: &sensor_msgs::msg::Joy) -> TouchPad {
+        let axes = msg.axes.as_slice();
+        let buttons = msg.buttons.as_slice();
+        let point = |active: usize, x: usize, y: usize| match (buttons.get(active), axes.get(x), axes.get(y)) {
+            (Some(1), Some(&x), Some(&y)) => Some(TouchPoint { x, y }),
+            _ => None,
+        };
+        TouchPad {
+            points: [
+                point(TOUCH_DUALSENSE::ACTIVE0, TOUCH_DUALSENSE::X0, TOUCH_DUALSENSE::Y0),
+                point(TOUCH_DUALSENSE::ACTIVE1, TOUCH_DUALSENSE::X1, TOUCH_DUALSENSE::Y1),
+            ],
+        }
+    }
+    pub fn touching(&self) -> usize {
+        self.points.iter().flatten().count()
+    }
+}
+
+/// Subscribes to the topics named by `IMU_TOPIC_ENV` and `TOUCH_TOPIC_ENV`,
+/// merges their messages into `state` and hands each new snapshot to
+/// `on_state`.
+#[cfg(feature = "ros")]
+pub fn add_sensor_subscribers(
+    node: &Arc<Node>,
+    selector: &mut Selector,
+    state: SharedState,
+    on_state: Rc<dyn Fn(ControllerState)>,
+) -> Result<(), DynError> {
+    if let Ok(topic) = std::env::var(IMU_TOPIC_ENV) {
+        let subscriber = node.create_subscriber::<sensor_msgs::msg::Imu>(&topic, None)?;
+        let state = state.clone();
+        let on_state = on_state.clone();
+        selector.add_subscriber(
+            subscriber,
+            Box::new(move |msg| on_state(state.update(|state| state.set_imu(&msg)))),
+        );
+    }
+    if let Ok(topic) = std::env::var(TOUCH_TOPIC_ENV) {
+        let subscriber = node.create_subscriber::<sensor_msgs::msg::Joy>(&topic, None)?;
+        selector.add_subscriber(
+            subscriber,
+            Box::new(move |msg| on_state(state.update(|state| state.set_touch(&msg)))),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tilt_from_gravity() {
+        let flat = ImuReading { linear_acceleration: [0.0, 0.0, 9.8], ..Default::default() };
+        assert_eq!(flat.tilt(), (0.0, 0.0));
+        let rolled = ImuReading { linear_acceleration: [0.0, 9.8, 0.0], ..Default::default() };
+        assert!((rolled.tilt().0 - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+        let pitched = ImuReading { linear_acceleration: [-9.8, 0.0, 0.0], ..Default::default() };
+        assert!((pitched.tilt().1 - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+    }
+
+    #[cfg(feature = "ros")]
+    #[test]
+    fn touch_from_joy() {
+        let mut msg = sensor_msgs::msg::Joy::new().unwrap();
+        assert_eq!(TouchPad::from_joy(&msg).touching(), 0);
+        msg.axes = safe_drive::msg::F32Seq::new(4).unwrap();
+        msg.axes.as_slice_mut().copy_from_slice(&[0.25, 0.5, 0.75, 1.0]);
+        msg.buttons = safe_drive::msg::I32Seq::new(2).unwrap();
+        msg.buttons.as_slice_mut().copy_from_slice(&[0, 1]);
+        let touchpad = TouchPad::from_joy(&msg);
+        assert_eq!(touchpad.points, [None, Some(TouchPoint { x: 0.75, y: 1.0 })]);
+    }
+}
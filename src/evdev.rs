@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::mem::{offset_of, size_of};
+use std::ops::RangeInclusive;
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::controller::ControllerState;
+use crate::log::{pr_warn, Logger};
+use crate::ps5_dualsense::{AXES_DUALSENSE, BUTTONS_DUALSENSE};
+
+/// Environment variable naming the `/dev/input/event*` device to read
+/// instead of subscribing to `joy`.
+pub const EVDEV_DEVICE_ENV: &str = "P9N_EVDEV_DEVICE";
+
+/// Size of `struct input_event` on this target.
+pub const EVENT_SIZE: usize = size_of::<RawInputEvent>();
+
+/// `struct input_event` from linux/input.h, for its size and field offsets.
+#[repr(C)]
+struct RawInputEvent {
+    time: libc::timeval,
+    type_: u16,
+    code: u16,
+    value: i32,
+}
+
+const TYPE_OFFSET: usize = offset_of!(RawInputEvent, type_);
+const CODE_OFFSET: usize = offset_of!(RawInputEvent, code);
+const VALUE_OFFSET: usize = offset_of!(RawInputEvent, value);
+
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+pub const EV_ABS: u16 = 0x03;
+pub const SYN_REPORT: u16 = 0;
+pub const SYN_DROPPED: u16 = 3;
+
+/// `(evdev code, BUTTONS_DUALSENSE index)` as reported by hid-playstation.
+const KEYS: [(u16, usize); 13] = [
+    (0x130, BUTTONS_DUALSENSE::CROSS),
+    (0x131, BUTTONS_DUALSENSE::CIRCLE),
+    (0x133, BUTTONS_DUALSENSE::TRIANGLE),
+    (0x134, BUTTONS_DUALSENSE::SQUARE),
+    (0x136, BUTTONS_DUALSENSE::L1),
+    (0x137, BUTTONS_DUALSENSE::R1),
+    (0x138, BUTTONS_DUALSENSE::L2),
+    (0x139, BUTTONS_DUALSENSE::R2),
+    (0x13a, BUTTONS_DUALSENSE::SELECT),
+    (0x13b, BUTTONS_DUALSENSE::START),
+    (0x13c, BUTTONS_DUALSENSE::PS),
+    (0x13d, BUTTONS_DUALSENSE::STICK_L_PUSH),
+    (0x13e, BUTTONS_DUALSENSE::STICK_R_PUSH),
+];
+
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const ABS_Z: u16 = 0x02;
+const ABS_RX: u16 = 0x03;
+const ABS_RY: u16 = 0x04;
+const ABS_RZ: u16 = 0x05;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT0Y: u16 = 0x11;
+/// The stick and trigger axes, whose ranges are queried from the device.
+const ANALOG_AXES: [u16; 6] = [ABS_X, ABS_Y, ABS_Z, ABS_RX, ABS_RY, ABS_RZ];
+/// What hid-playstation reports for the analog axes; used for recordings
+/// and axes the device does not describe.
+const DEFAULT_RANGE: RangeInclusive<i32> = 0..=255;
+
+/// `EVIOCGABS(code)`: `_IOR('E', 0x40 + code, struct input_absinfo)`.
+const fn eviocgabs(code: u16) -> u64 {
+    const IOC_READ: u64 = 2;
+    (IOC_READ << 30) | ((size_of::<libc::input_absinfo>() as u64) << 16) | ((b'E' as u64) << 8) | (0x40 + code as u64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    pub type_: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+impl InputEvent {
+    /// Decodes one `struct input_event`, ignoring its timestamp.
+    pub fn from_bytes(bytes: &[u8; EVENT_SIZE]) -> InputEvent {
+        let field = |offset: usize, len: usize| &bytes[offset..offset + len];
+        InputEvent {
+            type_: u16::from_ne_bytes(field(TYPE_OFFSET, 2).try_into().unwrap()),
+            code: u16::from_ne_bytes(field(CODE_OFFSET, 2).try_into().unwrap()),
+            value: i32::from_ne_bytes(field(VALUE_OFFSET, 4).try_into().unwrap()),
+        }
+    }
+    pub fn to_bytes(&self) -> [u8; EVENT_SIZE] {
+        let mut bytes = [0; EVENT_SIZE];
+        bytes[TYPE_OFFSET..TYPE_OFFSET + 2].copy_from_slice(&self.type_.to_ne_bytes());
+        bytes[CODE_OFFSET..CODE_OFFSET + 2].copy_from_slice(&self.code.to_ne_bytes());
+        bytes[VALUE_OFFSET..VALUE_OFFSET + 4].copy_from_slice(&self.value.to_ne_bytes());
+        bytes
+    }
+}
+
+/// Reads events from an evdev device, or from a recorded stream of them.
+pub struct EvdevReader<R> {
+    inner: R,
+}
+
+impl EvdevReader<File> {
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<EvdevReader<File>> {
+        Ok(EvdevReader::new(File::open(path.into())?))
+    }
+    /// The minimum and maximum the device reports for absolute axis `code`.
+    pub fn abs_range(&self, code: u16) -> io::Result<RangeInclusive<i32>> {
+        let mut info = libc::input_absinfo { value: 0, minimum: 0, maximum: 0, fuzz: 0, flat: 0, resolution: 0 };
+        // SAFETY: EVIOCGABS writes one `input_absinfo` through the pointer,
+        // which points at a live one.
+        let result = unsafe { libc::ioctl(self.inner.as_raw_fd(), eviocgabs(code) as _, &mut info) };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(info.minimum..=info.maximum)
+    }
+    /// A mapper scaling each analog axis by the range the device reports,
+    /// keeping the default for axes it cannot describe.
+    pub fn mapper(&self) -> EvdevMapper {
+        ANALOG_AXES.iter().fold(EvdevMapper::new(), |mapper, &code| match self.abs_range(code) {
+            Ok(range) => mapper.with_range(code, range),
+            Err(_) => mapper,
+        })
+    }
+}
+
+impl<R: Read> EvdevReader<R> {
+    pub fn new(inner: R) -> EvdevReader<R> {
+        EvdevReader { inner }
+    }
+    /// Blocks for the next event. Returns `None` at the end of a recording.
+    pub fn next_event(&mut self) -> io::Result<Option<InputEvent>> {
+        let mut bytes = [0; EVENT_SIZE];
+        match self.inner.read_exact(&mut bytes) {
+            Ok(()) => Ok(Some(InputEvent::from_bytes(&bytes))),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Folds evdev events into a controller snapshot, one per `SYN_REPORT`.
+pub struct EvdevMapper {
+    state: ControllerState,
+    dropped: bool,
+    ranges: HashMap<u16, RangeInclusive<i32>>,
+}
+
+impl EvdevMapper {
+    /// Scales every analog axis from `DEFAULT_RANGE`.
+    pub fn new() -> EvdevMapper {
+        EvdevMapper {
+            state: ControllerState::default(),
+            dropped: false,
+            ranges: HashMap::new(),
+        }
+    }
+    /// Scales axis `code` from `range` instead. Empty ranges are ignored.
+    pub fn with_range(mut self, code: u16, range: RangeInclusive<i32>) -> EvdevMapper {
+        if range.start() < range.end() {
+            self.ranges.insert(code, range);
+        }
+        self
+    }
+    pub fn range(&self, code: u16) -> RangeInclusive<i32> {
+        self.ranges.get(&code).cloned().unwrap_or(DEFAULT_RANGE)
+    }
+    pub fn state(&self) -> &ControllerState {
+        &self.state
+    }
+    /// Applies `event` and returns the snapshot when a frame is complete.
+    /// Events after a `SYN_DROPPED` are skipped until the next report, which
+    /// then returns the released state: a release may have been lost, so
+    /// held buttons have to be pressed again.
+    pub fn handle(&mut self, event: InputEvent) -> Option<ControllerState> {
+        match (event.type_, event.code) {
+            (EV_SYN, SYN_REPORT) => {
+                if std::mem::take(&mut self.dropped) {
+                    self.state = ControllerState::default();
+                }
+                return Some(self.state.clone());
+            }
+            (EV_SYN, SYN_DROPPED) => self.dropped = true,
+            _ if self.dropped => {}
+            (EV_KEY, code) => {
+                if let Some((_, button)) = KEYS.iter().find(|(key, _)| *key == code) {
+                    self.state.buttons[*button] = event.value != 0;
+                }
+            }
+            (EV_ABS, code) => {
+                let value = event.value;
+                let range = self.range(code);
+                let axes = &mut self.state.axes;
+                match code {
+                    ABS_X => axes[AXES_DUALSENSE::STICK_LX] = stick(value, range),
+                    ABS_Y => axes[AXES_DUALSENSE::STICK_LY] = stick(value, range),
+                    ABS_RX => axes[AXES_DUALSENSE::STICK_RX] = stick(value, range),
+                    ABS_RY => axes[AXES_DUALSENSE::STICK_RY] = stick(value, range),
+                    ABS_Z => axes[AXES_DUALSENSE::L2] = trigger(value, range),
+                    ABS_RZ => axes[AXES_DUALSENSE::R2] = trigger(value, range),
+                    ABS_HAT0X => axes[AXES_DUALSENSE::DPAD_X] = -value.signum() as f32,
+                    ABS_HAT0Y => axes[AXES_DUALSENSE::DPAD_Y] = -value.signum() as f32,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+impl Default for EvdevMapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Same conventions as the HID parser: left and up are +1, and the centre
+/// of an even-sized range such as 0..=255 reads as 0.
+fn stick(value: i32, range: RangeInclusive<i32>) -> f32 {
+    let (min, max) = (*range.start() as f32, *range.end() as f32);
+    let centre = ((min + max) / 2.0).ceil();
+    ((centre - value as f32) / (max - centre).max(1.0)).clamp(-1.0, 1.0)
+}
+
+/// Released at the minimum (1.0) to fully pressed at the maximum (-1.0).
+fn trigger(value: i32, range: RangeInclusive<i32>) -> f32 {
+    let (min, max) = (*range.start(), *range.end());
+    1.0 - 2.0 * (value.clamp(min, max) - min) as f32 / (max - min).max(1) as f32
+}
+
+/// Sends a snapshot per frame until the stream ends or fails, then the
+/// released state so nothing stays held. Returns why it stopped, or `None`
+/// if the receiver went away.
+fn forward<R: Read>(
+    mut reader: EvdevReader<R>,
+    mut mapper: EvdevMapper,
+    sender: &Sender<ControllerState>,
+) -> Option<String> {
+    let reason = loop {
+        match reader.next_event() {
+            Ok(Some(event)) => {
+                if let Some(state) = mapper.handle(event) {
+                    if sender.send(state).is_err() {
+                        return None;
+                    }
+                }
+            }
+            Ok(None) => break "device closed".to_string(),
+            Err(e) => break e.to_string(),
+        }
+    };
+    let _ = sender.send(ControllerState::default());
+    Some(reason)
+}
+
+/// Reads `path` on its own thread and sends a snapshot per frame, scaling
+/// the axes by the ranges the device reports. The receiver is an
+/// `InputSource`. When the device fails or is unplugged the
+/// error is logged and a released snapshot is sent before the thread exits.
+pub fn spawn_evdev(path: impl Into<PathBuf>) -> io::Result<Receiver<ControllerState>> {
+    let path = path.into();
+    let reader = EvdevReader::open(&path)?;
+    let mapper = reader.mapper();
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        if let Some(reason) = forward(reader, mapper, &sender) {
+            pr_warn!(Logger::new("p9n_interface_2024"), "{}: {}", path.display(), reason);
+        }
+    });
+    Ok(receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording(events: &[(u16, u16, i32)]) -> Vec<u8> {
+        events
+            .iter()
+            .flat_map(|&(type_, code, value)| InputEvent { type_, code, value }.to_bytes())
+            .collect()
+    }
+
+    fn replay(bytes: &[u8]) -> Vec<ControllerState> {
+        let mut reader = EvdevReader::new(bytes);
+        let mut mapper = EvdevMapper::new();
+        let mut frames = Vec::new();
+        while let Some(event) = reader.next_event().unwrap() {
+            frames.extend(mapper.handle(event));
+        }
+        frames
+    }
+
+    #[test]
+    fn frames_from_recorded_stream() {
+        let bytes = recording(&[
+            (EV_KEY, 0x130, 1),
+            (EV_ABS, ABS_X, 0),
+            (EV_ABS, ABS_HAT0X, 1),
+            (EV_SYN, SYN_REPORT, 0),
+            (EV_KEY, 0x130, 0),
+            (EV_ABS, ABS_RZ, 255),
+            (EV_SYN, SYN_REPORT, 0),
+        ]);
+        let frames = replay(&bytes[..bytes.len() - 3]);
+        assert_eq!(frames.len(), 1);
+        let frames = replay(&bytes);
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].buttons[BUTTONS_DUALSENSE::CROSS]);
+        assert_eq!(frames[0].axes[AXES_DUALSENSE::STICK_LX], 1.0);
+        assert_eq!(frames[0].axes[AXES_DUALSENSE::DPAD_X], -1.0);
+        assert_eq!(frames[0].axes[AXES_DUALSENSE::R2], 1.0);
+        assert!(!frames[1].buttons[BUTTONS_DUALSENSE::CROSS]);
+        assert_eq!(frames[1].axes[AXES_DUALSENSE::R2], -1.0);
+    }
+
+    #[test]
+    fn drop_resets_to_released() {
+        let frames = replay(&recording(&[
+            (EV_KEY, 0x131, 1),
+            (EV_ABS, ABS_HAT0Y, -1),
+            (EV_SYN, SYN_REPORT, 0),
+            // The circle and d-pad releases were in the dropped events.
+            (EV_SYN, SYN_DROPPED, 0),
+            (EV_KEY, 0x130, 1),
+            (EV_SYN, SYN_REPORT, 0),
+            (EV_KEY, 0x133, 1),
+            (EV_SYN, SYN_REPORT, 0),
+        ]));
+        assert_eq!(frames.len(), 3);
+        assert!(frames[0].buttons[BUTTONS_DUALSENSE::CIRCLE]);
+        assert_eq!(frames[1], ControllerState::default());
+        assert!(!frames[2].buttons[BUTTONS_DUALSENSE::CIRCLE]);
+        assert!(!frames[2].buttons[BUTTONS_DUALSENSE::CROSS]);
+        assert_eq!(frames[2].axes[AXES_DUALSENSE::DPAD_Y], 0.0);
+        assert!(frames[2].buttons[BUTTONS_DUALSENSE::TRIANGLE]);
+    }
+
+    struct Unplugged<'a>(&'a [u8]);
+
+    impl Read for Unplugged<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::other("no such device"));
+            }
+            self.0.read(buf)
+        }
+    }
+
+    #[test]
+    fn releases_everything_when_the_device_goes() {
+        let bytes = recording(&[(EV_KEY, 0x130, 1), (EV_SYN, SYN_REPORT, 0)]);
+        let (sender, receiver) = mpsc::channel();
+        let reason = forward(EvdevReader::new(Unplugged(&bytes)), EvdevMapper::new(), &sender);
+        assert_eq!(reason.as_deref(), Some("no such device"));
+        let frames: Vec<ControllerState> = receiver.try_iter().collect();
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].buttons[BUTTONS_DUALSENSE::CROSS]);
+        assert_eq!(frames[1], ControllerState::default());
+        let (sender, receiver) = mpsc::channel();
+        assert_eq!(
+            forward(EvdevReader::new(&bytes[..]), EvdevMapper::new(), &sender).as_deref(),
+            Some("device closed")
+        );
+        assert_eq!(receiver.try_iter().last(), Some(ControllerState::default()));
+    }
+
+    #[test]
+    fn axes_scale_by_the_reported_range() {
+        let mut mapper = EvdevMapper::new().with_range(ABS_X, -32768..=32767).with_range(ABS_RZ, 0..=1023);
+        for (code, value) in [(ABS_X, -32768), (ABS_RZ, 1023)] {
+            mapper.handle(InputEvent { type_: EV_ABS, code, value });
+        }
+        let frame = mapper.handle(InputEvent { type_: EV_SYN, code: SYN_REPORT, value: 0 }).unwrap();
+        assert_eq!(frame.axes[AXES_DUALSENSE::STICK_LX], 1.0);
+        assert_eq!(frame.axes[AXES_DUALSENSE::R2], -1.0);
+        assert_eq!(stick(0, -32768..=32767), 0.0);
+        assert_eq!(stick(32767, -32768..=32767), -1.0);
+        assert_eq!(stick(128, DEFAULT_RANGE), 0.0);
+        assert_eq!(trigger(0, 0..=1023), 1.0);
+        assert_eq!(mapper.range(ABS_Y), DEFAULT_RANGE);
+        assert_eq!(EvdevMapper::new().with_range(ABS_Y, 5..=5).range(ABS_Y), DEFAULT_RANGE);
+    }
+}
@@ -0,0 +1,294 @@
+#[cfg(feature = "ros")]
+use std::cell::RefCell;
+#[cfg(feature = "ros")]
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "ros")]
+use safe_drive::{msg::common_interfaces::sensor_msgs, selector::Selector, topic::publisher::Publisher};
+
+#[cfg(feature = "ros")]
+use crate::clock::Clock;
+
+/// Environment variable naming the controller driver's feedback topic.
+pub const FEEDBACK_TOPIC_ENV: &str = "P9N_FEEDBACK_TOPIC";
+pub const DEFAULT_FEEDBACK_TOPIC: &str = "/joy/set_feedback";
+pub const FEEDBACK_PERIOD: Duration = Duration::from_millis(20);
+
+/// `sensor_msgs/JoyFeedback` type constants.
+pub const TYPE_LED: u8 = 0;
+pub const TYPE_RUMBLE: u8 = 1;
+/// `TYPE_LED` ids 0 to 2 are the lightbar channels; the player LEDs follow,
+/// left to right.
+pub const PLAYER_LED_ID: u8 = 3;
+pub const PLAYER_LED_COUNT: u8 = 5;
+
+/// The topic from `FEEDBACK_TOPIC_ENV`, or `DEFAULT_FEEDBACK_TOPIC`.
+pub fn feedback_topic() -> String {
+    std::env::var(FEEDBACK_TOPIC_ENV).unwrap_or(DEFAULT_FEEDBACK_TOPIC.to_string())
+}
+
+/// Lightbar colour with channels from 0.0 to 1.0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Rgb {
+    pub const OFF: Rgb = Rgb::new(0.0, 0.0, 0.0);
+    pub const RED: Rgb = Rgb::new(1.0, 0.0, 0.0);
+    pub const GREEN: Rgb = Rgb::new(0.0, 1.0, 0.0);
+    pub const BLUE: Rgb = Rgb::new(0.0, 0.0, 1.0);
+
+    pub const fn new(r: f32, g: f32, b: f32) -> Rgb {
+        Rgb { r, g, b }
+    }
+}
+
+/// What the lightbar shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Indicator {
+    Solid(Rgb),
+    /// Alternates between `color` and off, spending `period / 2` in each.
+    Blink { color: Rgb, period: Duration },
+}
+
+impl Indicator {
+    /// The colour shown `elapsed` after the indicator was set.
+    pub fn color(&self, elapsed: Duration) -> Rgb {
+        match *self {
+            Indicator::Solid(color) => color,
+            Indicator::Blink { color, period } => {
+                let half = period.as_millis().max(2) / 2;
+                if (elapsed.as_millis() / half) & 1 == 0 {
+                    color
+                } else {
+                    Rgb::OFF
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rumble {
+    pub intensity: f32,
+    pub duration: Duration,
+}
+
+/// Things worth telling the operator about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A joint ran into its soft limit.
+    Clamp,
+    EStop,
+    Armed,
+    Disarmed,
+    ModeChange,
+    AirLow,
+    /// An interlock refused an output.
+    Blocked,
+}
+
+impl Event {
+    pub fn rumble(&self) -> Rumble {
+        let (intensity, millis) = match self {
+            Event::Clamp => (0.3, 100),
+            Event::EStop => (1.0, 800),
+            Event::Armed => (0.6, 200),
+            Event::Disarmed => (0.6, 100),
+            Event::ModeChange => (0.4, 150),
+            Event::AirLow => (1.0, 500),
+            Event::Blocked => (0.8, 250),
+        };
+        Rumble {
+            intensity,
+            duration: Duration::from_millis(millis),
+        }
+    }
+}
+
+/// One `sensor_msgs/JoyFeedback` entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeedbackItem {
+    pub type_: u8,
+    pub id: u8,
+    pub intensity: f32,
+}
+
+/// Desired rumble, lightbar and player LED state. `tick_at` returns the full
+/// state whenever it differs from what was last sent.
+pub struct ControllerFeedback {
+    rumble: Option<(f32, Instant)>,
+    indicator: Indicator,
+    indicator_since: Instant,
+    player_leds: u8,
+    sent: Option<Vec<FeedbackItem>>,
+}
+
+impl ControllerFeedback {
+    /// Feedback whose blink phases count from `now`.
+    pub fn new_at(now: Instant) -> ControllerFeedback {
+        ControllerFeedback {
+            rumble: None,
+            indicator: Indicator::Solid(Rgb::OFF),
+            indicator_since: now,
+            player_leds: 0,
+            sent: None,
+        }
+    }
+    pub fn signal_at(&mut self, event: Event, now: Instant) {
+        self.rumble_at(event.rumble(), now);
+    }
+    /// Starts `rumble` unless a stronger one is still running.
+    pub fn rumble_at(&mut self, rumble: Rumble, now: Instant) {
+        if let Some((intensity, until)) = self.rumble {
+            if now < until && intensity > rumble.intensity {
+                return;
+            }
+        }
+        self.rumble = Some((rumble.intensity, now + rumble.duration));
+    }
+    pub fn indicator(&self) -> Indicator {
+        self.indicator
+    }
+    /// Changes the indicator; setting the current one again keeps its blink
+    /// phase.
+    pub fn set_indicator_at(&mut self, indicator: Indicator, now: Instant) {
+        if indicator != self.indicator {
+            self.indicator = indicator;
+            self.indicator_since = now;
+        }
+    }
+    pub fn player_leds(&self) -> u8 {
+        self.player_leds
+    }
+    /// Lights the player LEDs set in `mask`, bit 0 being the leftmost.
+    pub fn set_player_leds(&mut self, mask: u8) {
+        self.player_leds = mask & ((1 << PLAYER_LED_COUNT) - 1);
+    }
+    pub fn tick_at(&mut self, now: Instant) -> Option<Vec<FeedbackItem>> {
+        let intensity = match self.rumble {
+            Some((intensity, until)) if now < until => intensity,
+            _ => {
+                self.rumble = None;
+                0.0
+            }
+        };
+        let lightbar = self.indicator.color(now.saturating_duration_since(self.indicator_since));
+        let mut items = vec![
+            FeedbackItem { type_: TYPE_RUMBLE, id: 0, intensity },
+            FeedbackItem { type_: TYPE_RUMBLE, id: 1, intensity },
+            FeedbackItem { type_: TYPE_LED, id: 0, intensity: lightbar.r },
+            FeedbackItem { type_: TYPE_LED, id: 1, intensity: lightbar.g },
+            FeedbackItem { type_: TYPE_LED, id: 2, intensity: lightbar.b },
+        ];
+        items.extend((0..PLAYER_LED_COUNT).map(|led| FeedbackItem {
+            type_: TYPE_LED,
+            id: PLAYER_LED_ID + led,
+            intensity: if self.player_leds & (1 << led) != 0 { 1.0 } else { 0.0 },
+        }));
+        if self.sent.as_ref() == Some(&items) {
+            return None;
+        }
+        self.sent = Some(items.clone());
+        Some(items)
+    }
+}
+
+#[cfg(feature = "ros")]
+pub fn feedback_msg(items: &[FeedbackItem]) -> sensor_msgs::msg::JoyFeedbackArray {
+    let mut msg = sensor_msgs::msg::JoyFeedbackArray::new().unwrap();
+    msg.array = sensor_msgs::msg::JoyFeedbackSeq::new(items.len()).unwrap();
+    for (entry, item) in msg.array.as_slice_mut().iter_mut().zip(items) {
+        entry.type_ = item.type_;
+        entry.id = item.id;
+        entry.intensity = item.intensity;
+    }
+    msg
+}
+
+/// Adds a wall timer that sends the feedback state whenever it changes.
+#[cfg(feature = "ros")]
+pub fn add_feedback_timer(
+    selector: &mut Selector,
+    feedback: Rc<RefCell<ControllerFeedback>>,
+    publisher: Publisher<sensor_msgs::msg::JoyFeedbackArray>,
+    clock: Rc<dyn Clock>,
+) -> u64 {
+    selector.add_wall_timer(
+        "feedback",
+        FEEDBACK_PERIOD,
+        Box::new(move || {
+            if let Some(items) = feedback.borrow_mut().tick_at(clock.now()) {
+                let _ = publisher.send(&feedback_msg(&items));
+            }
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rumble_of(items: &[FeedbackItem]) -> f32 {
+        items[0].intensity
+    }
+
+    #[test]
+    fn rumble_expires_and_only_changes_are_sent() {
+        let start = Instant::now();
+        let mut feedback = ControllerFeedback::new_at(start);
+        assert_eq!(feedback.tick_at(start).map(|items| rumble_of(&items)), Some(0.0));
+        assert_eq!(feedback.tick_at(start), None);
+        feedback.rumble_at(Event::Armed.rumble(), start);
+        assert_eq!(feedback.tick_at(start).map(|items| rumble_of(&items)), Some(0.6));
+        assert_eq!(feedback.tick_at(start + Duration::from_millis(100)), None);
+        assert_eq!(
+            feedback.tick_at(start + Duration::from_millis(200)).map(|items| rumble_of(&items)),
+            Some(0.0)
+        );
+        feedback.set_indicator_at(Indicator::Solid(Rgb::GREEN), start);
+        let items = feedback.tick_at(start + Duration::from_millis(220)).unwrap();
+        assert_eq!(items[3], FeedbackItem { type_: TYPE_LED, id: 1, intensity: 1.0 });
+    }
+
+    #[test]
+    fn player_leds_are_masked() {
+        let start = Instant::now();
+        let mut feedback = ControllerFeedback::new_at(start);
+        feedback.tick_at(start);
+        feedback.set_player_leds(0b1110_0100);
+        assert_eq!(feedback.player_leds(), 0b0_0100);
+        let items = feedback.tick_at(start).unwrap();
+        let lit: Vec<u8> = items[5..].iter().filter(|item| item.intensity > 0.0).map(|item| item.id).collect();
+        assert_eq!(lit, vec![PLAYER_LED_ID + 2]);
+        assert!(items[5..].iter().all(|item| item.type_ == TYPE_LED));
+    }
+
+    #[test]
+    fn blink_alternates() {
+        let start = Instant::now();
+        let mut feedback = ControllerFeedback::new_at(start);
+        let blink = Indicator::Blink { color: Rgb::RED, period: Duration::from_millis(500) };
+        feedback.set_indicator_at(blink, start);
+        let red = |items: Vec<FeedbackItem>| items[2].intensity;
+        assert_eq!(feedback.tick_at(start).map(red), Some(1.0));
+        assert_eq!(feedback.tick_at(start + Duration::from_millis(200)), None);
+        assert_eq!(feedback.tick_at(start + Duration::from_millis(250)).map(red), Some(0.0));
+        feedback.set_indicator_at(blink, start + Duration::from_millis(300));
+        assert_eq!(feedback.tick_at(start + Duration::from_millis(500)).map(red), Some(1.0));
+    }
+
+    #[test]
+    fn weaker_rumble_does_not_cut_stronger_short() {
+        let start = Instant::now();
+        let mut feedback = ControllerFeedback::new_at(start);
+        feedback.rumble_at(Event::EStop.rumble(), start);
+        feedback.rumble_at(Event::Clamp.rumble(), start + Duration::from_millis(10));
+        let items = feedback.tick_at(start + Duration::from_millis(500)).unwrap();
+        assert_eq!(rumble_of(&items), 1.0);
+    }
+}
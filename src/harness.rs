@@ -0,0 +1,224 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::behaviour::Behaviour;
+use crate::clock::{Clock, ManualClock};
+use crate::commands::{MdCommand, PointCommand, SdCommand};
+use crate::script::Script;
+use crate::sink::Sink;
+
+/// Set to rewrite the golden files instead of comparing against them.
+pub const UPDATE_GOLDEN_ENV: &str = "P9N_UPDATE_GOLDEN";
+
+struct Recorder {
+    clock: Rc<ManualClock>,
+    lines: RefCell<Vec<String>>,
+}
+
+impl Recorder {
+    fn record(&self, topic: &str, body: String) {
+        let at = self.clock.elapsed().as_secs_f64();
+        self.lines.borrow_mut().push(format!("{:>7.3} {} {}", at, topic, body));
+    }
+}
+
+/// A sink that writes every message to the harness transcript.
+pub struct TopicSink {
+    recorder: Rc<Recorder>,
+    topic: &'static str,
+}
+
+impl Sink<MdCommand> for TopicSink {
+    fn send(&self, msg: &MdCommand) {
+        self.recorder.record(self.topic, msg.to_string());
+    }
+}
+
+impl Sink<SdCommand> for TopicSink {
+    fn send(&self, msg: &SdCommand) {
+        self.recorder.record(self.topic, msg.to_string());
+    }
+}
+
+impl Sink<PointCommand> for TopicSink {
+    fn send(&self, msg: &PointCommand) {
+        self.recorder.record(self.topic, msg.to_string());
+    }
+}
+
+impl Sink<String> for TopicSink {
+    fn send(&self, msg: &String) {
+        self.recorder.record(self.topic, msg.clone());
+    }
+}
+
+/// Runs a behaviour against scripted input on a virtual clock and records
+/// what it sends, one `seconds topic fields` line per message.
+pub struct Harness {
+    clock: Rc<ManualClock>,
+    recorder: Rc<Recorder>,
+}
+
+impl Harness {
+    pub fn new() -> Harness {
+        let clock = Rc::new(ManualClock::new());
+        let recorder = Rc::new(Recorder {
+            clock: clock.clone(),
+            lines: RefCell::new(Vec::new()),
+        });
+        Harness { clock, recorder }
+    }
+    pub fn clock(&self) -> &ManualClock {
+        &self.clock
+    }
+    pub fn sink(&self, topic: &'static str) -> Box<TopicSink> {
+        Box::new(TopicSink {
+            recorder: self.recorder.clone(),
+            topic,
+        })
+    }
+    /// Plays `script` from the current virtual time. Input and ticks due at
+    /// the same instant run input first.
+    pub fn run(&self, behaviour: &mut dyn Behaviour, script: &Script) {
+        let start = self.clock.elapsed();
+        let end = start + script.duration();
+        let period = behaviour.tick_period().filter(|period| !period.is_zero());
+        let mut next_tick = period.map(|period| {
+            let ticks = start.as_nanos() / period.as_nanos() + 1;
+            period * ticks as u32
+        });
+        for (offset, state) in script.frames() {
+            let at = start + offset;
+            while let (Some(tick), Some(period)) = (next_tick, period) {
+                if tick >= at {
+                    break;
+                }
+                self.clock.set_elapsed(tick);
+                behaviour.tick_at(self.clock.now());
+                next_tick = Some(tick + period);
+            }
+            self.clock.set_elapsed(at);
+            behaviour.on_state_at(state, self.clock.now());
+        }
+        while let (Some(tick), Some(period)) = (next_tick, period) {
+            if tick > end {
+                break;
+            }
+            self.clock.set_elapsed(tick);
+            behaviour.tick_at(self.clock.now());
+            next_tick = Some(tick + period);
+        }
+        self.clock.set_elapsed(end);
+    }
+    pub fn transcript(&self) -> String {
+        self.recorder.lines.borrow().iter().map(|line| format!("{}\n", line)).collect()
+    }
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compares `actual` with `tests/golden/<name>.txt`, or rewrites that file
+/// when `UPDATE_GOLDEN_ENV` is set.
+pub fn assert_golden(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.txt", name));
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("{}: {} (run with {}=1 to create it)", path.display(), e, UPDATE_GOLDEN_ENV));
+    if expected != actual {
+        panic!(
+            "{} differs from the golden file; run with {}=1 to accept\n--- expected\n{}--- actual\n{}",
+            path.display(),
+            UPDATE_GOLDEN_ENV,
+            expected,
+            actual
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::controller::tests::joy_parts;
+    use crate::controller::ControllerState;
+    use crate::feedback::ControllerFeedback;
+    use crate::{robot1, robot2_1, robot2_2, robot2_3, robot2_4};
+
+    /// Keeps every `PointCommand` as `[md0, .., md5]`.
+    #[derive(Clone, Default)]
+    struct Capture(Rc<RefCell<Vec<[i16; 6]>>>);
+
+    impl Sink<PointCommand> for Capture {
+        fn send(&self, msg: &PointCommand) {
+            self.0.borrow_mut().push(msg.values());
+        }
+    }
+
+    /// Servo fields are 0 until first commanded, then stay in 25..=125.
+    fn assert_servos(sent: &[[i16; 6]], servos: &[usize]) -> Result<(), TestCaseError> {
+        for msg in sent {
+            for &index in servos {
+                let value = msg[index];
+                prop_assert!(value == 0 || (25..=125).contains(&value), "md{} = {} in {:?}", index, value, msg);
+            }
+        }
+        Ok(())
+    }
+
+    fn session() -> impl Strategy<Value = Script> {
+        prop::collection::vec((joy_parts(), 0..300u64), 0..30).prop_map(|frames| {
+            frames.into_iter().fold(Script::new(), |script, ((buttons, axes), ms)| {
+                script
+                    .state(ControllerState::from_joy_parts(&buttons, &axes))
+                    .wait(Duration::from_millis(ms))
+            })
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn robots_survive_any_joy_session(script in session()) {
+            let harness = Harness::new();
+            let feedback = Rc::new(RefCell::new(ControllerFeedback::new_at(harness.clock().now())));
+            let mut robot = robot1::Robot1::new_at(
+                harness.sink("/md_driver_topic"),
+                harness.sink("/sd_driver_topic"),
+                harness.sink("/air_status"),
+                feedback.clone(),
+                harness.clock().now(),
+            );
+            harness.run(&mut robot, &script);
+
+            let capture = Capture::default();
+            harness.run(&mut robot2_1::Robot2_1::new(Box::new(capture.clone())), &script);
+            assert_servos(&capture.0.borrow(), &[3])?;
+
+            let capture = Capture::default();
+            harness.run(&mut robot2_2::Robot2_2::new(Box::new(capture.clone()), feedback), &script);
+            assert_servos(&capture.0.borrow(), &[0, 1, 2, 3])?;
+
+            let capture = Capture::default();
+            harness.run(&mut robot2_3::Robot2_3::new(Box::new(capture.clone())), &script);
+            assert_servos(&capture.0.borrow(), &[2])?;
+
+            let capture = Capture::default();
+            harness.run(&mut robot2_4::Robot2_4::new(Box::new(capture.clone())), &script);
+            assert_servos(&capture.0.borrow(), &[0, 1, 3])?;
+        }
+    }
+}
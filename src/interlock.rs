@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// An actuator as seen by the interlocks. A motor counts as on while it is
+/// commanded or still applying power, so it stays on while it decelerates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Output {
+    Solenoid { address: u8, port: u8 },
+    Motor { address: u8 },
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Output::Solenoid { address, port } => write!(f, "SD {:#04x} port {}", address, port),
+            Output::Motor { address } => write!(f, "MD {:#04x}", address),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    On(Output),
+    Off(Output),
+}
+
+impl Condition {
+    fn output(&self) -> Output {
+        match self {
+            Condition::On(output) | Condition::Off(output) => *output,
+        }
+    }
+    fn holds(&self, on: bool) -> bool {
+        match self {
+            Condition::On(_) => on,
+            Condition::Off(_) => !on,
+        }
+    }
+}
+
+/// "`output` may only be on while `requires` holds."
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interlock {
+    pub name: &'static str,
+    pub output: Output,
+    pub requires: Condition,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blocked {
+    pub rule: &'static str,
+    pub output: Output,
+    pub on: bool,
+}
+
+impl fmt::Display for Blocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "turning {} {} blocked by interlock '{}'",
+            self.output,
+            if self.on { "on" } else { "off" },
+            self.rule
+        )
+    }
+}
+
+impl std::error::Error for Blocked {}
+
+/// Declarative interlock rules plus the last known state of every output
+/// they mention. A change is blocked if it would turn a guarded output on
+/// while its condition is false, or break the condition of a guarded output
+/// that is currently on.
+pub struct Interlocks {
+    rules: Vec<Interlock>,
+    state: HashMap<Output, bool>,
+}
+
+impl Interlocks {
+    pub fn new() -> Interlocks {
+        Interlocks {
+            rules: Vec::new(),
+            state: HashMap::new(),
+        }
+    }
+    pub fn rule(mut self, name: &'static str, output: Output, requires: Condition) -> Interlocks {
+        self.rules.push(Interlock { name, output, requires });
+        self
+    }
+    pub fn rules(&self) -> &[Interlock] {
+        &self.rules
+    }
+    pub fn is_on(&self, output: Output) -> bool {
+        *self.state.get(&output).unwrap_or(&false)
+    }
+    pub fn check(&self, output: Output, on: bool) -> Result<(), Blocked> {
+        for rule in &self.rules {
+            let guarded_on = if rule.output == output { on } else { self.is_on(rule.output) };
+            let condition_on = if rule.requires.output() == output {
+                on
+            } else {
+                self.is_on(rule.requires.output())
+            };
+            if guarded_on && !rule.requires.holds(condition_on) {
+                return Err(Blocked { rule: rule.name, output, on });
+            }
+        }
+        Ok(())
+    }
+    /// Records a change that has been applied, checked or not.
+    pub fn set_state(&mut self, output: Output, on: bool) {
+        self.state.insert(output, on);
+    }
+}
+
+impl Default for Interlocks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXHAUST: Output = Output::Solenoid { address: 0x00, port: 0 };
+    const FIRE: Output = Output::Solenoid { address: 0x00, port: 1 };
+
+    #[test]
+    fn guards_both_directions() {
+        let mut interlocks = Interlocks::new().rule("fire needs closed exhaust", FIRE, Condition::On(EXHAUST));
+        assert!(interlocks.check(FIRE, true).is_err());
+        assert!(interlocks.check(EXHAUST, true).is_ok());
+        interlocks.set_state(EXHAUST, true);
+        assert!(interlocks.check(FIRE, true).is_ok());
+        interlocks.set_state(FIRE, true);
+        let blocked = interlocks.check(EXHAUST, false).unwrap_err();
+        assert_eq!(blocked.rule, "fire needs closed exhaust");
+        interlocks.set_state(FIRE, false);
+        assert!(interlocks.check(EXHAUST, false).is_ok());
+        // Turning the guarded output off is always allowed.
+        assert!(interlocks.check(FIRE, false).is_ok());
+    }
+
+    #[test]
+    fn off_condition() {
+        let motor = Output::Motor { address: 0x05 };
+        let lock = Output::Solenoid { address: 0x05, port: 0 };
+        let mut interlocks = Interlocks::new().rule("no drive while locked", motor, Condition::Off(lock));
+        interlocks.set_state(lock, true);
+        assert!(interlocks.check(motor, true).is_err());
+        interlocks.set_state(lock, false);
+        interlocks.set_state(motor, true);
+        assert!(interlocks.check(lock, true).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+use crate::servo::finite_range;
+
+/// Conversion from an integrated joint position to the value written to a
+/// driver channel. Integer outputs round and saturate instead of wrapping.
+pub trait JointOutput: Copy {
+    fn from_position(position: f32) -> Self;
+}
+
+impl JointOutput for f32 {
+    fn from_position(position: f32) -> Self {
+        position
+    }
+}
+
+macro_rules! impl_joint_output {
+    ($($t:ty),*) => {
+        $(impl JointOutput for $t {
+            fn from_position(position: f32) -> Self {
+                // `as` from float saturates at the integer bounds.
+                position.round() as $t
+            }
+        })*
+    };
+}
+impl_joint_output!(i8, i16, i32, u8, u16);
+
+/// Integrates a commanded velocity into a joint position over real time.
+///
+/// `command` is in -1.0..=1.0 and is scaled by `max_velocity` (units per
+/// second). Within `soft_zone` of either limit, motion towards that limit is
+/// slowed down proportionally to the remaining distance.
+pub struct JointIntegrator<T: JointOutput> {
+    position: f32,
+    min: f32,
+    max: f32,
+    max_velocity: f32,
+    soft_zone: f32,
+    min_soft_scale: f32,
+    max_step: Duration,
+    command: f32,
+    last_update: Option<Instant>,
+    _output: PhantomData<T>,
+}
+
+impl<T: JointOutput> JointIntegrator<T> {
+    /// Non-finite limits are handled like `Servo::new`; a non-finite
+    /// `initial` starts at `min` and a non-finite velocity never moves.
+    pub fn new(initial: f32, min: f32, max: f32, max_velocity: f32) -> JointIntegrator<T> {
+        let (min, max) = finite_range(min, max);
+        JointIntegrator {
+            position: if initial.is_finite() { initial.clamp(min, max) } else { min },
+            min,
+            max,
+            max_velocity: if max_velocity.is_finite() { max_velocity.abs() } else { 0.0 },
+            soft_zone: 0.0,
+            min_soft_scale: 0.2,
+            max_step: Duration::from_millis(100),
+            command: 0.0,
+            last_update: None,
+            _output: PhantomData,
+        }
+    }
+    pub fn with_soft_zone(mut self, soft_zone: f32, min_scale: f32) -> JointIntegrator<T> {
+        self.soft_zone = soft_zone.max(0.0);
+        self.min_soft_scale = min_scale.clamp(0.0, 1.0);
+        self
+    }
+    /// Longest interval integrated in one update, so a stalled input stream
+    /// cannot turn into a jump.
+    pub fn with_max_step(mut self, max_step: Duration) -> JointIntegrator<T> {
+        self.max_step = max_step;
+        self
+    }
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+    pub fn output(&self) -> T {
+        T::from_position(self.position)
+    }
+    pub fn min(&self) -> f32 {
+        self.min
+    }
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+    pub fn at_limit(&self) -> bool {
+        self.position <= self.min || self.position >= self.max
+    }
+    /// Moves to `position` immediately, e.g. when restoring a stored pose.
+    pub fn reset(&mut self, position: f32) {
+        if position.is_finite() {
+            self.position = position.clamp(self.min, self.max);
+        }
+        self.command = 0.0;
+        self.last_update = None;
+    }
+    /// The previous command is held until `now`, so a press only starts
+    /// moving the joint from the next update on.
+    pub fn update_at(&mut self, command: f32, now: Instant) -> T {
+        let dt = match self.last_update {
+            Some(last) => now.saturating_duration_since(last).min(self.max_step),
+            None => Duration::ZERO,
+        };
+        let velocity = self.command * self.max_velocity * self.soft_scale(self.command);
+        self.position = (self.position + velocity * dt.as_secs_f32()).clamp(self.min, self.max);
+        self.command = if command.is_finite() { command.clamp(-1.0, 1.0) } else { 0.0 };
+        self.last_update = Some(now);
+        self.output()
+    }
+    fn soft_scale(&self, command: f32) -> f32 {
+        if self.soft_zone <= 0.0 {
+            return 1.0;
+        }
+        let distance = if command > 0.0 {
+            self.max - self.position
+        } else {
+            self.position - self.min
+        };
+        (distance / self.soft_zone).clamp(self.min_soft_scale, 1.0)
+    }
+}
+
+/// Maps a DualSense trigger axis (1.0 released, -1.0 fully pressed) to a
+/// command in 0.0..=1.0.
+pub fn trigger_command(axis: f32) -> f32 {
+    if axis.is_finite() {
+        ((1.0 - axis) / 2.0).clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// Maps a pair of buttons to a command of -1.0, 0.0 or 1.0.
+pub fn button_command(increase: bool, decrease: bool) -> f32 {
+    match (increase, decrease) {
+        (true, false) => 1.0,
+        (false, true) => -1.0,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integrates_over_elapsed_time() {
+        let start = Instant::now();
+        let mut joint = JointIntegrator::<i8>::new(25.0, 25.0, 125.0, 50.0);
+        assert_eq!(joint.update_at(1.0, start), 25);
+        assert_eq!(joint.update_at(1.0, start + Duration::from_millis(100)), 30);
+        assert_eq!(joint.update_at(-1.0, start + Duration::from_millis(140)), 32);
+        assert_eq!(joint.update_at(0.0, start + Duration::from_millis(180)), 30);
+        // Idle time before the next press is not integrated.
+        assert_eq!(joint.update_at(1.0, start + Duration::from_secs(10)), 30);
+    }
+
+    #[test]
+    fn clamps_to_limits_and_caps_step() {
+        let start = Instant::now();
+        let mut joint = JointIntegrator::<i16>::new(120.0, 25.0, 125.0, 1000.0);
+        joint.update_at(1.0, start);
+        assert_eq!(joint.update_at(1.0, start + Duration::from_secs(5)), 125);
+        assert!(joint.at_limit());
+        let mut joint = JointIntegrator::<f32>::new(25.0, 25.0, 125.0, 10.0);
+        joint.update_at(1.0, start);
+        assert_eq!(joint.update_at(1.0, start + Duration::from_secs(5)), 26.0);
+    }
+
+    #[test]
+    fn slows_down_in_soft_zone() {
+        let start = Instant::now();
+        let mut joint = JointIntegrator::<f32>::new(120.0, 25.0, 125.0, 100.0).with_soft_zone(10.0, 0.1);
+        joint.update_at(1.0, start);
+        let position = joint.update_at(1.0, start + Duration::from_millis(10));
+        assert!((position - 120.5).abs() < 1e-3);
+        // Moving away from the limit is not slowed.
+        joint.update_at(-1.0, start + Duration::from_millis(20));
+        let position = joint.update_at(-1.0, start + Duration::from_millis(30));
+        assert!((position - 119.95).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ignores_non_finite_commands() {
+        let start = Instant::now();
+        let mut joint = JointIntegrator::<i8>::new(50.0, 25.0, 125.0, 50.0);
+        joint.update_at(f32::NAN, start);
+        joint.update_at(f32::INFINITY, start + Duration::from_millis(50));
+        assert_eq!(joint.update_at(0.0, start + Duration::from_millis(100)), 50);
+        joint.reset(f32::NAN);
+        assert_eq!(joint.output(), 50);
+        assert_eq!(trigger_command(f32::NAN), 0.0);
+        assert_eq!(trigger_command(-1.0), 1.0);
+        assert_eq!(trigger_command(1.0), 0.0);
+    }
+
+    #[test]
+    fn non_finite_limits_do_not_panic() {
+        let start = Instant::now();
+        let mut joint = JointIntegrator::<i8>::new(50.0, f32::NAN, f32::NAN, 50.0);
+        joint.update_at(1.0, start);
+        assert_eq!(joint.update_at(1.0, start + Duration::from_millis(100)), 0);
+        let mut joint = JointIntegrator::<i8>::new(f32::NAN, 25.0, f32::INFINITY, f32::NAN);
+        joint.update_at(1.0, start);
+        assert_eq!(joint.update_at(1.0, start + Duration::from_millis(100)), 25);
+    }
+}
@@ -0,0 +1,300 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::controller::{ControllerState, AXIS_COUNT, BUTTON_COUNT};
+
+/// File header: magic, format version, button and axis counts.
+///
+/// Version 1 holds the Joy message only. IMU and touchpad readings are not
+/// recorded and come back as `None` and no touches, so a replayed session
+/// cannot drive gyro aiming or touchpad controls. Adding them needs a new
+/// version.
+pub const MAGIC: [u8; 4] = *b"P9NJ";
+pub const VERSION: u8 = 1;
+/// Offset in microseconds (u64), buttons as a bitmask (u16), then the axes
+/// as f32, all little endian.
+pub const FRAME_SIZE: usize = 8 + 2 + 4 * AXIS_COUNT;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// Time since the start of the recording.
+    pub offset: Duration,
+    pub state: ControllerState,
+}
+
+impl Frame {
+    pub fn to_bytes(&self) -> [u8; FRAME_SIZE] {
+        let mut bytes = [0; FRAME_SIZE];
+        bytes[0..8].copy_from_slice(&(self.offset.as_micros() as u64).to_le_bytes());
+        let mask = self
+            .state
+            .buttons
+            .iter()
+            .enumerate()
+            .fold(0u16, |mask, (i, pressed)| mask | (*pressed as u16) << i);
+        bytes[8..10].copy_from_slice(&mask.to_le_bytes());
+        for (i, axis) in self.state.axes.iter().enumerate() {
+            bytes[10 + 4 * i..14 + 4 * i].copy_from_slice(&axis.to_le_bytes());
+        }
+        bytes
+    }
+    pub fn from_bytes(bytes: &[u8; FRAME_SIZE]) -> Frame {
+        let micros = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        let mask = u16::from_le_bytes([bytes[8], bytes[9]]);
+        let mut state = ControllerState::default();
+        for (i, button) in state.buttons.iter_mut().enumerate() {
+            *button = mask & (1 << i) != 0;
+        }
+        for (i, axis) in state.axes.iter_mut().enumerate() {
+            *axis = f32::from_le_bytes(bytes[10 + 4 * i..14 + 4 * i].try_into().unwrap());
+        }
+        Frame {
+            offset: Duration::from_micros(micros),
+            state,
+        }
+    }
+}
+
+/// Streams frames to a file as they arrive, so a killed recorder loses at
+/// most the frame it was writing.
+pub struct LogWriter<W: Write> {
+    inner: W,
+    start: Option<Instant>,
+}
+
+impl LogWriter<File> {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<LogWriter<File>> {
+        LogWriter::new(File::create(path)?)
+    }
+}
+
+impl<W: Write> LogWriter<W> {
+    pub fn new(mut inner: W) -> io::Result<LogWriter<W>> {
+        inner.write_all(&MAGIC)?;
+        inner.write_all(&[VERSION, BUTTON_COUNT as u8, AXIS_COUNT as u8])?;
+        Ok(LogWriter { inner, start: None })
+    }
+    /// Records `state`; the first call marks the start of the recording.
+    pub fn record_at(&mut self, state: &ControllerState, now: Instant) -> io::Result<()> {
+        let start = *self.start.get_or_insert(now);
+        let frame = Frame {
+            offset: now.saturating_duration_since(start),
+            state: state.clone(),
+        };
+        self.inner.write_all(&frame.to_bytes())?;
+        self.inner.flush()
+    }
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub frames: Vec<Frame>,
+}
+
+impl Recording {
+    /// Reads a whole log. A truncated last frame is dropped.
+    pub fn read_from(mut reader: impl Read) -> io::Result<Recording> {
+        let mut header = [0; 7];
+        reader.read_exact(&mut header)?;
+        if header[0..4] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a joy log"));
+        }
+        if header[4..7] != [VERSION, BUTTON_COUNT as u8, AXIS_COUNT as u8] {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported joy log version {} ({} buttons, {} axes)", header[4], header[5], header[6]),
+            ));
+        }
+        let mut frames = Vec::new();
+        let mut bytes = [0; FRAME_SIZE];
+        loop {
+            match reader.read_exact(&mut bytes) {
+                Ok(()) => frames.push(Frame::from_bytes(&bytes)),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Recording { frames })
+    }
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Recording> {
+        Recording::read_from(BufReader::new(File::open(path)?))
+    }
+    pub fn duration(&self) -> Duration {
+        self.frames.last().map_or(Duration::ZERO, |frame| frame.offset)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Speed {
+    /// Replay with the recorded timing scaled by this factor; 1.0 is the
+    /// original speed and 2.0 twice as fast.
+    Scaled(f64),
+    /// Only advance on `step`.
+    Step,
+}
+
+/// Replays a recording frame by frame as `poll_at` is called with the
+/// node's clock.
+pub struct Player {
+    recording: Recording,
+    speed: Speed,
+    looped: bool,
+    next: usize,
+    /// Time at which the recording's offset zero plays.
+    origin: Option<Instant>,
+    seek_to: Duration,
+}
+
+impl Player {
+    pub fn new(recording: Recording) -> Player {
+        Player {
+            recording,
+            speed: Speed::Scaled(1.0),
+            looped: false,
+            next: 0,
+            origin: None,
+            seek_to: Duration::ZERO,
+        }
+    }
+    pub fn with_speed(mut self, speed: Speed) -> Player {
+        self.speed = speed;
+        self
+    }
+    pub fn with_loop(mut self, looped: bool) -> Player {
+        self.looped = looped;
+        self
+    }
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+    /// Offset of the next frame to play.
+    pub fn position(&self) -> Duration {
+        self.recording
+            .frames
+            .get(self.next)
+            .map_or(self.recording.duration(), |frame| frame.offset)
+    }
+    pub fn is_finished(&self) -> bool {
+        !self.looped && self.next >= self.recording.frames.len()
+    }
+    /// Jumps to the first frame at or after `offset`. Timed playback
+    /// continues from there on the next poll.
+    pub fn seek(&mut self, offset: Duration) {
+        self.next = self.recording.frames.partition_point(|frame| frame.offset < offset);
+        self.origin = None;
+        self.seek_to = offset;
+    }
+    /// Returns the next frame regardless of timing.
+    pub fn step(&mut self) -> Option<ControllerState> {
+        if self.next >= self.recording.frames.len() {
+            if !self.looped || self.recording.frames.is_empty() {
+                return None;
+            }
+            self.next = 0;
+        }
+        self.next += 1;
+        Some(self.recording.frames[self.next - 1].state.clone())
+    }
+    /// Returns the next frame once it is due. In `Step` mode this never
+    /// returns anything.
+    pub fn poll_at(&mut self, now: Instant) -> Option<ControllerState> {
+        let Speed::Scaled(factor) = self.speed else {
+            return None;
+        };
+        if self.recording.frames.is_empty() {
+            return None;
+        }
+        if self.next >= self.recording.frames.len() {
+            if !self.looped {
+                return None;
+            }
+            self.seek(Duration::ZERO);
+        }
+        let origin = *self
+            .origin
+            .get_or_insert_with(|| now - self.seek_to.div_f64(factor));
+        let offset = self.recording.frames[self.next].offset;
+        if now.saturating_duration_since(origin) < offset.div_f64(factor) {
+            return None;
+        }
+        self.step()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dualsense_sensors::{ImuReading, TouchPoint};
+    use crate::ps5_dualsense::{AXES_DUALSENSE, BUTTONS_DUALSENSE};
+
+    fn session() -> (Vec<u8>, Vec<ControllerState>) {
+        let start = Instant::now();
+        let mut states = Vec::new();
+        let mut writer = LogWriter::new(Vec::new()).unwrap();
+        for i in 0..3 {
+            let mut state = ControllerState::default();
+            state.buttons[BUTTONS_DUALSENSE::CROSS] = i == 1;
+            state.axes[AXES_DUALSENSE::STICK_LX] = i as f32 * 0.5;
+            writer.record_at(&state, start + Duration::from_millis(100 * i)).unwrap();
+            states.push(state);
+        }
+        (writer.into_inner(), states)
+    }
+
+    #[test]
+    fn round_trip_and_truncation() {
+        let (bytes, states) = session();
+        assert_eq!(bytes.len(), 7 + 3 * FRAME_SIZE);
+        let recording = Recording::read_from(bytes.as_slice()).unwrap();
+        let read: Vec<ControllerState> = recording.frames.iter().map(|f| f.state.clone()).collect();
+        assert_eq!(read, states);
+        assert_eq!(recording.duration(), Duration::from_millis(200));
+        let truncated = Recording::read_from(&bytes[..bytes.len() - 5]).unwrap();
+        assert_eq!(truncated.frames.len(), 2);
+        assert!(Recording::read_from(&b"nope\x01\x0d\x08"[..]).is_err());
+    }
+
+    #[test]
+    fn sensors_are_not_recorded() {
+        let mut state = ControllerState {
+            imu: Some(ImuReading::default()),
+            ..Default::default()
+        };
+        state.touchpad.points[0] = Some(TouchPoint { x: 0.5, y: 0.5 });
+        let mut writer = LogWriter::new(Vec::new()).unwrap();
+        writer.record_at(&state, Instant::now()).unwrap();
+        let recording = Recording::read_from(writer.into_inner().as_slice()).unwrap();
+        assert_eq!(recording.frames[0].state, ControllerState::default());
+    }
+
+    #[test]
+    fn scaled_loop_and_seek() {
+        let (bytes, states) = session();
+        let recording = Recording::read_from(bytes.as_slice()).unwrap();
+        let start = Instant::now();
+        let mut player = Player::new(recording.clone()).with_speed(Speed::Scaled(2.0));
+        assert_eq!(player.poll_at(start), Some(states[0].clone()));
+        assert_eq!(player.poll_at(start + Duration::from_millis(40)), None);
+        assert_eq!(player.poll_at(start + Duration::from_millis(50)), Some(states[1].clone()));
+        assert_eq!(player.poll_at(start + Duration::from_millis(100)), Some(states[2].clone()));
+        assert!(player.is_finished());
+        assert_eq!(player.poll_at(start + Duration::from_secs(1)), None);
+
+        let mut player = Player::new(recording.clone()).with_loop(true);
+        player.seek(Duration::from_millis(150));
+        assert_eq!(player.position(), Duration::from_millis(200));
+        assert_eq!(player.poll_at(start), None);
+        assert_eq!(player.poll_at(start + Duration::from_millis(50)), Some(states[2].clone()));
+        assert_eq!(player.poll_at(start + Duration::from_millis(60)), Some(states[0].clone()));
+
+        let mut player = Player::new(recording).with_speed(Speed::Step).with_loop(true);
+        assert_eq!(player.poll_at(start + Duration::from_secs(1)), None);
+        let stepped: Vec<_> = (0..4).filter_map(|_| player.step()).collect();
+        assert_eq!(stepped, vec![states[0].clone(), states[1].clone(), states[2].clone(), states[0].clone()]);
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::controller::ControllerState;
+use crate::ps5_dualsense::{AXES_DUALSENSE, BUTTONS_DUALSENSE};
+
+/// Terminals only report key presses and auto-repeat, never releases, so a
+/// key counts as held until this long after its last byte. It has to cover
+/// the usual 250-500 ms auto-repeat delay, and it is also how late every
+/// release arrives.
+pub const HOLD_TIMEOUT: Duration = Duration::from_millis(550);
+/// Environment variable overriding `HOLD_TIMEOUT`, in milliseconds. Set it
+/// just above the terminal's repeat delay (`xset q` on X11) to cut the
+/// release latency.
+pub const HOLD_TIMEOUT_ENV: &str = "P9N_KEY_HOLD_MS";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Splits raw terminal bytes into keys, decoding arrow escape sequences.
+#[derive(Debug, Default)]
+pub struct KeyParser {
+    pending: Vec<u8>,
+}
+
+impl KeyParser {
+    pub fn new() -> KeyParser {
+        KeyParser::default()
+    }
+    pub fn push(&mut self, byte: u8) -> Option<Key> {
+        self.pending.push(byte);
+        let key = match self.pending.as_slice() {
+            [0x1b] | [0x1b, b'['] => return None,
+            [0x1b, b'[', b'A'] => Some(Key::Up),
+            [0x1b, b'[', b'B'] => Some(Key::Down),
+            [0x1b, b'[', b'C'] => Some(Key::Right),
+            [0x1b, b'[', b'D'] => Some(Key::Left),
+            [0x1b, ..] => None,
+            [byte] if byte.is_ascii() => Some(Key::Char(*byte as char)),
+            _ => None,
+        };
+        self.pending.clear();
+        key
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    Button(usize),
+    /// An axis pushed to `value` while the key is held.
+    Axis(usize, f32),
+    /// L2/R2: the digital button plus its analog axis fully pressed.
+    Trigger(usize, usize),
+}
+
+/// `(key, binding, legend label)` for the default layout.
+pub const DEFAULT_BINDINGS: [(Key, Binding, &str); 25] = [
+    (Key::Char('w'), Binding::Axis(AXES_DUALSENSE::STICK_LY, 1.0), "left stick up"),
+    (Key::Char('s'), Binding::Axis(AXES_DUALSENSE::STICK_LY, -1.0), "left stick down"),
+    (Key::Char('a'), Binding::Axis(AXES_DUALSENSE::STICK_LX, 1.0), "left stick left"),
+    (Key::Char('d'), Binding::Axis(AXES_DUALSENSE::STICK_LX, -1.0), "left stick right"),
+    (Key::Char('i'), Binding::Axis(AXES_DUALSENSE::STICK_RY, 1.0), "right stick up"),
+    (Key::Char('k'), Binding::Axis(AXES_DUALSENSE::STICK_RY, -1.0), "right stick down"),
+    (Key::Char('j'), Binding::Axis(AXES_DUALSENSE::STICK_RX, 1.0), "right stick left"),
+    (Key::Char('l'), Binding::Axis(AXES_DUALSENSE::STICK_RX, -1.0), "right stick right"),
+    (Key::Up, Binding::Axis(AXES_DUALSENSE::DPAD_Y, 1.0), "d-pad up"),
+    (Key::Down, Binding::Axis(AXES_DUALSENSE::DPAD_Y, -1.0), "d-pad down"),
+    (Key::Left, Binding::Axis(AXES_DUALSENSE::DPAD_X, 1.0), "d-pad left"),
+    (Key::Right, Binding::Axis(AXES_DUALSENSE::DPAD_X, -1.0), "d-pad right"),
+    (Key::Char('z'), Binding::Button(BUTTONS_DUALSENSE::CROSS), "cross"),
+    (Key::Char('x'), Binding::Button(BUTTONS_DUALSENSE::CIRCLE), "circle"),
+    (Key::Char('c'), Binding::Button(BUTTONS_DUALSENSE::SQUARE), "square"),
+    (Key::Char('v'), Binding::Button(BUTTONS_DUALSENSE::TRIANGLE), "triangle"),
+    (Key::Char('q'), Binding::Button(BUTTONS_DUALSENSE::L1), "L1"),
+    (Key::Char('e'), Binding::Button(BUTTONS_DUALSENSE::R1), "R1"),
+    (Key::Char('1'), Binding::Trigger(BUTTONS_DUALSENSE::L2, AXES_DUALSENSE::L2), "L2"),
+    (Key::Char('3'), Binding::Trigger(BUTTONS_DUALSENSE::R2, AXES_DUALSENSE::R2), "R2"),
+    (Key::Char('n'), Binding::Button(BUTTONS_DUALSENSE::SELECT), "select"),
+    (Key::Char('m'), Binding::Button(BUTTONS_DUALSENSE::START), "start"),
+    (Key::Char('p'), Binding::Button(BUTTONS_DUALSENSE::PS), "PS"),
+    (Key::Char('f'), Binding::Button(BUTTONS_DUALSENSE::STICK_L_PUSH), "L3"),
+    (Key::Char('h'), Binding::Button(BUTTONS_DUALSENSE::STICK_R_PUSH), "R3"),
+];
+
+/// Turns key presses into controller snapshots. A lowercase key is held for
+/// the hold timeout after its last repeat; the uppercase key latches it until
+/// pressed again.
+pub struct KeyboardState {
+    bindings: Vec<(Key, Binding, &'static str)>,
+    hold_timeout: Duration,
+    last_seen: HashMap<Key, Instant>,
+    latched: HashSet<Key>,
+}
+
+impl KeyboardState {
+    pub fn new() -> KeyboardState {
+        KeyboardState {
+            bindings: DEFAULT_BINDINGS.to_vec(),
+            hold_timeout: HOLD_TIMEOUT,
+            last_seen: HashMap::new(),
+            latched: HashSet::new(),
+        }
+    }
+    /// The default layout with the hold timeout from `HOLD_TIMEOUT_ENV`, or
+    /// `HOLD_TIMEOUT` when unset or invalid.
+    pub fn from_env() -> KeyboardState {
+        let hold_timeout = std::env::var(HOLD_TIMEOUT_ENV)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .filter(|ms: &u64| *ms > 0)
+            .map(Duration::from_millis)
+            .unwrap_or(HOLD_TIMEOUT);
+        KeyboardState::new().with_hold_timeout(hold_timeout)
+    }
+    pub fn with_hold_timeout(mut self, hold_timeout: Duration) -> KeyboardState {
+        self.hold_timeout = hold_timeout;
+        self
+    }
+    pub fn hold_timeout(&self) -> Duration {
+        self.hold_timeout
+    }
+    pub fn key_at(&mut self, key: Key, now: Instant) {
+        match key {
+            Key::Char(c) if c.is_ascii_uppercase() => {
+                let key = Key::Char(c.to_ascii_lowercase());
+                if !self.latched.remove(&key) {
+                    self.latched.insert(key);
+                }
+            }
+            _ => {
+                self.last_seen.insert(key, now);
+            }
+        }
+    }
+    pub fn is_held_at(&self, key: Key, now: Instant) -> bool {
+        self.latched.contains(&key)
+            || self
+                .last_seen
+                .get(&key)
+                .is_some_and(|seen| now.saturating_duration_since(*seen) < self.hold_timeout)
+    }
+    pub fn state_at(&self, now: Instant) -> ControllerState {
+        let mut state = ControllerState::default();
+        for (key, binding, _) in &self.bindings {
+            if !self.is_held_at(*key, now) {
+                continue;
+            }
+            match *binding {
+                Binding::Button(button) => state.buttons[button] = true,
+                Binding::Axis(axis, value) => state.axes[axis] = value,
+                Binding::Trigger(button, axis) => {
+                    state.buttons[button] = true;
+                    state.axes[axis] = -1.0;
+                }
+            }
+        }
+        state
+    }
+    /// One line per binding, latched keys marked with `*`, then how long a
+    /// held key takes to release.
+    pub fn legend(&self) -> String {
+        let keys: String = self
+            .bindings
+            .iter()
+            .map(|(key, _, label)| {
+                let name = match key {
+                    Key::Char(c) => c.to_string(),
+                    Key::Up => "↑".to_string(),
+                    Key::Down => "↓".to_string(),
+                    Key::Left => "←".to_string(),
+                    Key::Right => "→".to_string(),
+                };
+                let latched = if self.latched.contains(key) { "*" } else { " " };
+                format!("{}{:>2}  {}\n", latched, name, label)
+            })
+            .collect();
+        format!(
+            "{}\nheld keys release {} ms after the last repeat ({})\n",
+            keys,
+            self.hold_timeout.as_millis(),
+            HOLD_TIMEOUT_ENV
+        )
+    }
+}
+
+impl Default for KeyboardState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_arrows_and_chars() {
+        let mut parser = KeyParser::new();
+        let keys: Vec<Key> = b"z\x1b[Ax\x1b[D".iter().filter_map(|b| parser.push(*b)).collect();
+        assert_eq!(keys, vec![Key::Char('z'), Key::Up, Key::Char('x'), Key::Left]);
+    }
+
+    #[test]
+    fn hold_and_latch() {
+        let start = Instant::now();
+        let mut keyboard = KeyboardState::new();
+        keyboard.key_at(Key::Char('z'), start);
+        keyboard.key_at(Key::Char('1'), start);
+        let state = keyboard.state_at(start + Duration::from_millis(100));
+        assert!(state.buttons[BUTTONS_DUALSENSE::CROSS]);
+        assert_eq!(state.axes[AXES_DUALSENSE::L2], -1.0);
+        assert!(!keyboard.state_at(start + HOLD_TIMEOUT).buttons[BUTTONS_DUALSENSE::CROSS]);
+
+        keyboard.key_at(Key::Char('V'), start);
+        assert!(keyboard.state_at(start + Duration::from_secs(10)).buttons[BUTTONS_DUALSENSE::TRIANGLE]);
+        assert!(keyboard.legend().contains("* v  triangle"));
+        keyboard.key_at(Key::Char('V'), start);
+        assert!(!keyboard.state_at(start).buttons[BUTTONS_DUALSENSE::TRIANGLE]);
+    }
+
+    #[test]
+    fn shorter_hold_timeout() {
+        let start = Instant::now();
+        let mut keyboard = KeyboardState::new().with_hold_timeout(Duration::from_millis(300));
+        keyboard.key_at(Key::Char('z'), start);
+        assert!(keyboard.state_at(start + Duration::from_millis(299)).buttons[BUTTONS_DUALSENSE::CROSS]);
+        assert!(!keyboard.state_at(start + Duration::from_millis(300)).buttons[BUTTONS_DUALSENSE::CROSS]);
+        assert!(keyboard.legend().contains("release 300 ms"));
+    }
+}
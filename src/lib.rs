@@ -1,2 +1,36 @@
+pub mod air_budget;
+pub mod behaviour;
+pub mod clock;
+pub mod commands;
+pub mod controller;
+pub mod dualsense_hid;
+pub mod dualsense_sensors;
+pub mod evdev;
+pub mod feedback;
+pub mod harness;
+pub mod interlock;
+pub mod joint;
+pub mod joy_log;
+pub mod keyboard;
+pub mod log;
+pub mod macro_sequence;
+pub mod mock_driver;
+pub mod mode;
+pub mod motion;
+pub mod motor;
+pub mod outputs;
 pub mod p9n_interface;
-pub mod ps5_dualsense;
\ No newline at end of file
+pub mod persist;
+pub mod point_channel;
+pub mod pose;
+pub mod ps5_dualsense;
+pub mod rate_limit;
+pub mod robot1;
+pub mod robot2_1;
+pub mod robot2_2;
+pub mod robot2_3;
+pub mod robot2_4;
+pub mod script;
+pub mod servo;
+pub mod sink;
+pub mod solenoid;
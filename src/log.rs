@@ -0,0 +1,50 @@
+use std::fmt;
+
+/// The logger the behaviours write to: safe_drive's under `ros`, stderr
+/// without it, so the robots build and run in tests with no ROS install.
+pub struct Logger {
+    #[cfg(feature = "ros")]
+    inner: safe_drive::logger::Logger,
+    #[cfg(not(feature = "ros"))]
+    name: String,
+}
+
+impl Logger {
+    pub fn new(name: &str) -> Logger {
+        Logger {
+            #[cfg(feature = "ros")]
+            inner: safe_drive::logger::Logger::new(name),
+            #[cfg(not(feature = "ros"))]
+            name: name.to_string(),
+        }
+    }
+    pub fn info(&self, args: fmt::Arguments) {
+        #[cfg(feature = "ros")]
+        safe_drive::pr_info!(self.inner, "{}", args);
+        #[cfg(not(feature = "ros"))]
+        eprintln!("[INFO] [{}]: {}", self.name, args);
+    }
+    pub fn warn(&self, args: fmt::Arguments) {
+        #[cfg(feature = "ros")]
+        safe_drive::pr_warn!(self.inner, "{}", args);
+        #[cfg(not(feature = "ros"))]
+        eprintln!("[WARN] [{}]: {}", self.name, args);
+    }
+}
+
+/// `pr_info!` for a `log::Logger`.
+macro_rules! pr_info {
+    ($logger:expr, $($arg:tt)*) => {
+        $logger.info(format_args!($($arg)*))
+    };
+}
+
+/// `pr_warn!` for a `log::Logger`.
+macro_rules! pr_warn {
+    ($logger:expr, $($arg:tt)*) => {
+        $logger.warn(format_args!($($arg)*))
+    };
+}
+
+pub(crate) use pr_info;
+pub(crate) use pr_warn;
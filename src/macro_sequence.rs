@@ -0,0 +1,174 @@
+use std::time::{Duration, Instant};
+
+pub const MACRO_PERIOD: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroStep<C> {
+    Command(C),
+    Wait(Duration),
+}
+
+/// A scripted list of output commands with delays between them, plus the
+/// commands that bring the outputs to a safe state when it is cancelled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroSequence<C> {
+    name: &'static str,
+    steps: Vec<MacroStep<C>>,
+    on_cancel: Vec<C>,
+}
+
+impl<C: Clone> MacroSequence<C> {
+    pub fn new(name: &'static str) -> MacroSequence<C> {
+        MacroSequence {
+            name,
+            steps: Vec::new(),
+            on_cancel: Vec::new(),
+        }
+    }
+    pub fn then(mut self, command: C) -> MacroSequence<C> {
+        self.steps.push(MacroStep::Command(command));
+        self
+    }
+    pub fn wait(mut self, duration: Duration) -> MacroSequence<C> {
+        self.steps.push(MacroStep::Wait(duration));
+        self
+    }
+    pub fn on_cancel(mut self, command: C) -> MacroSequence<C> {
+        self.on_cancel.push(command);
+        self
+    }
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+    pub fn steps(&self) -> &[MacroStep<C>] {
+        &self.steps
+    }
+    /// Sum of all waits.
+    pub fn duration(&self) -> Duration {
+        self.steps
+            .iter()
+            .map(|step| match step {
+                MacroStep::Wait(duration) => *duration,
+                MacroStep::Command(_) => Duration::ZERO,
+            })
+            .sum()
+    }
+}
+
+struct Running<C> {
+    sequence: MacroSequence<C>,
+    index: usize,
+    next_at: Instant,
+}
+
+/// Plays one `MacroSequence` at a time. `tick_at` is meant to be called from a
+/// selector wall timer so waits never block Joy processing.
+pub struct MacroRunner<C> {
+    running: Option<Running<C>>,
+}
+
+impl<C: Clone> MacroRunner<C> {
+    pub fn new() -> MacroRunner<C> {
+        MacroRunner { running: None }
+    }
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+    pub fn current(&self) -> Option<&'static str> {
+        self.running.as_ref().map(|running| running.sequence.name())
+    }
+    /// Starts `sequence` and returns the commands due right away. Returns
+    /// `None` without starting if another sequence is still running.
+    pub fn start_at(&mut self, sequence: &MacroSequence<C>, now: Instant) -> Option<Vec<C>> {
+        if self.running.is_some() {
+            return None;
+        }
+        self.running = Some(Running {
+            sequence: sequence.clone(),
+            index: 0,
+            next_at: now,
+        });
+        Some(self.tick_at(now))
+    }
+    /// Stops the running sequence and returns its cancel commands.
+    pub fn cancel(&mut self) -> Vec<C> {
+        match self.running.take() {
+            Some(running) => running.sequence.on_cancel,
+            None => Vec::new(),
+        }
+    }
+    /// Returns every command whose wait has elapsed by `now`.
+    pub fn tick_at(&mut self, now: Instant) -> Vec<C> {
+        let mut commands = Vec::new();
+        let Some(running) = self.running.as_mut() else {
+            return commands;
+        };
+        while running.index < running.sequence.steps.len() && now >= running.next_at {
+            match &running.sequence.steps[running.index] {
+                MacroStep::Command(command) => commands.push(command.clone()),
+                MacroStep::Wait(duration) => running.next_at += *duration,
+            }
+            running.index += 1;
+        }
+        if running.index >= running.sequence.steps.len() && now >= running.next_at {
+            self.running = None;
+        }
+        commands
+    }
+}
+
+impl<C: Clone> Default for MacroRunner<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fire() -> MacroSequence<&'static str> {
+        MacroSequence::new("fire")
+            .then("close")
+            .wait(Duration::from_millis(100))
+            .then("fire")
+            .wait(Duration::from_millis(300))
+            .then("release")
+            .then("open")
+            .on_cancel("release")
+            .on_cancel("open")
+    }
+
+    #[test]
+    fn plays_steps_on_schedule() {
+        let start = Instant::now();
+        let mut runner = MacroRunner::new();
+        assert_eq!(runner.start_at(&fire(), start), Some(vec!["close"]));
+        assert_eq!(runner.tick_at(start + Duration::from_millis(99)), Vec::<&str>::new());
+        assert_eq!(runner.tick_at(start + Duration::from_millis(100)), vec!["fire"]);
+        assert!(runner.is_running());
+        assert_eq!(runner.tick_at(start + Duration::from_millis(450)), vec!["release", "open"]);
+        assert!(!runner.is_running());
+        assert_eq!(fire().duration(), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn late_ticks_catch_up_in_order() {
+        let start = Instant::now();
+        let mut runner = MacroRunner::new();
+        runner.start_at(&fire(), start);
+        assert_eq!(runner.tick_at(start + Duration::from_secs(1)), vec!["fire", "release", "open"]);
+    }
+
+    #[test]
+    fn cancel_and_busy() {
+        let start = Instant::now();
+        let mut runner = MacroRunner::new();
+        runner.start_at(&fire(), start);
+        assert_eq!(runner.start_at(&fire(), start), None);
+        assert_eq!(runner.current(), Some("fire"));
+        assert_eq!(runner.cancel(), vec!["release", "open"]);
+        assert!(runner.cancel().is_empty());
+        assert_eq!(runner.tick_at(start + Duration::from_secs(1)), Vec::<&str>::new());
+    }
+}
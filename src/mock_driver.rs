@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Instant;
+
+use crate::commands::{MdCommand, PointCommand, SdCommand};
+use crate::point_channel::PointChannel;
+
+pub const MD_TOPIC: &str = "/md_driver_topic";
+pub const SD_TOPIC: &str = "/sd_driver_topic";
+pub const POINT_TOPICS: [&str; 4] = ["/point_2_1", "/point_2_2", "/point_2_3", "/point_2_4"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotorState {
+    pub mode: u8,
+    /// Power with the phase applied as its sign.
+    pub power: i32,
+    pub updated: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolenoidState {
+    pub on: bool,
+    /// When `on` last changed.
+    pub changed: Instant,
+    pub activations: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointState {
+    /// Raw channel values indexed by `PointChannel`; servo angles for
+    /// servo channels.
+    pub values: [i16; 6],
+    pub updated: Instant,
+}
+
+/// Simulated MD/SD driver boards and `PointDrive` receivers. Keeps the last
+/// command per motor address, solenoid address and port, and point topic.
+pub struct MockDriver {
+    start: Instant,
+    motors: BTreeMap<u8, MotorState>,
+    solenoids: BTreeMap<(u8, u8), SolenoidState>,
+    points: BTreeMap<String, PointState>,
+}
+
+impl MockDriver {
+    pub fn new_at(start: Instant) -> MockDriver {
+        MockDriver {
+            start,
+            motors: BTreeMap::new(),
+            solenoids: BTreeMap::new(),
+            points: BTreeMap::new(),
+        }
+    }
+    pub fn handle_md_at(&mut self, msg: &MdCommand, now: Instant) {
+        let power = if msg.phase { -(msg.power as i32) } else { msg.power as i32 };
+        self.motors.insert(msg.address, MotorState { mode: msg.mode, power, updated: now });
+    }
+    /// Solenoid boards switch a port on for any non-zero `power1`.
+    pub fn handle_sd_at(&mut self, msg: &SdCommand, now: Instant) {
+        let on = msg.is_on();
+        let state = self.solenoids.entry((msg.address, msg.port)).or_insert(SolenoidState {
+            on: false,
+            changed: now,
+            activations: 0,
+        });
+        if state.on != on {
+            state.on = on;
+            state.changed = now;
+            state.activations += on as u32;
+        }
+    }
+    pub fn handle_point_at(&mut self, topic: &str, msg: &PointCommand, now: Instant) {
+        self.points.insert(topic.to_string(), PointState { values: msg.values(), updated: now });
+    }
+    pub fn motor(&self, address: u8) -> Option<&MotorState> {
+        self.motors.get(&address)
+    }
+    pub fn solenoid(&self, address: u8, port: u8) -> Option<&SolenoidState> {
+        self.solenoids.get(&(address, port))
+    }
+    pub fn is_on(&self, address: u8, port: u8) -> bool {
+        self.solenoid(address, port).is_some_and(|state| state.on)
+    }
+    pub fn point(&self, topic: &str) -> Option<&PointState> {
+        self.points.get(topic)
+    }
+    pub fn channel(&self, topic: &str, channel: PointChannel) -> Option<i16> {
+        self.point(topic).map(|state| state.values[channel.index()])
+    }
+    /// One line per motor, solenoid and point topic, with the time of the
+    /// last change since the driver started.
+    pub fn summary(&self) -> String {
+        let since = |at: Instant| at.saturating_duration_since(self.start).as_secs_f64();
+        let mut out = String::new();
+        for (address, motor) in &self.motors {
+            let _ = writeln!(
+                out,
+                "md 0x{:02x}: mode {} power {:>5} @{:.2}s",
+                address,
+                motor.mode,
+                motor.power,
+                since(motor.updated)
+            );
+        }
+        for ((address, port), solenoid) in &self.solenoids {
+            let _ = writeln!(
+                out,
+                "sd 0x{:02x}.{}: {:<3} x{} @{:.2}s",
+                address,
+                port,
+                if solenoid.on { "on" } else { "off" },
+                solenoid.activations,
+                since(solenoid.changed)
+            );
+        }
+        for (topic, point) in &self.points {
+            let channels: Vec<String> = PointChannel::ALL
+                .iter()
+                .map(|channel| format!("{}={}", channel, point.values[channel.index()]))
+                .collect();
+            let _ = writeln!(out, "{}: {} @{:.2}s", topic, channels.join(" "), since(point.updated));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn tracks_outputs() {
+        let start = Instant::now();
+        let later = start + Duration::from_millis(250);
+        let mut driver = MockDriver::new_at(start);
+
+        let md = MdCommand { address: 0x05, phase: true, power: 300, ..Default::default() };
+        driver.handle_md_at(&md, start);
+        assert_eq!(driver.motor(0x05).unwrap().power, -300);
+
+        let mut sd = SdCommand { port: 1, power1: 1000, ..Default::default() };
+        driver.handle_sd_at(&sd, start);
+        driver.handle_sd_at(&sd, later);
+        assert!(driver.is_on(0x00, 1));
+        assert_eq!(driver.solenoid(0x00, 1).unwrap().changed, start);
+        sd.power1 = 0;
+        driver.handle_sd_at(&sd, later);
+        let solenoid = driver.solenoid(0x00, 1).unwrap();
+        assert_eq!((solenoid.on, solenoid.activations, solenoid.changed), (false, 1, later));
+
+        let point = PointCommand { md2: -45, ..Default::default() };
+        driver.handle_point_at("/point_2_2", &point, later);
+        assert_eq!(driver.channel("/point_2_2", PointChannel::Md2), Some(-45));
+        assert_eq!(driver.channel("/point_2_1", PointChannel::Md2), None);
+
+        let summary = driver.summary();
+        assert!(summary.contains("md 0x05: mode 0 power  -300 @0.00s"));
+        assert!(summary.contains("sd 0x00.1: off x1 @0.25s"));
+        assert!(summary.contains("/point_2_2: md0=0 md1=0 md2=-45"));
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::feedback::{ControllerFeedback, Event, Indicator, Rgb};
+
+/// Operating and safety state shown on the lightbar and player LEDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Disarmed,
+    Armed,
+    /// Armed, with drive outputs scaled down for fine positioning.
+    Precision,
+    EStop,
+}
+
+impl Mode {
+    /// Whether actuators may be driven in this mode.
+    pub fn is_armed(&self) -> bool {
+        matches!(self, Mode::Armed | Mode::Precision)
+    }
+}
+
+/// Mode-to-indicator and mode-to-player-LED bindings, plus the mode
+/// currently shown.
+pub struct ModeLights {
+    bindings: HashMap<Mode, Indicator>,
+    player_leds: HashMap<Mode, u8>,
+    mode: Mode,
+}
+
+impl ModeLights {
+    /// Starts in `mode` with disarmed red, armed green, precision blue and
+    /// e-stop blinking red. The player LEDs tell the modes apart without
+    /// colour: the outer two when disarmed, the middle one when armed, the
+    /// middle three in precision and all five on e-stop.
+    pub fn new(mode: Mode) -> ModeLights {
+        ModeLights {
+            bindings: HashMap::from([
+                (Mode::Disarmed, Indicator::Solid(Rgb::RED)),
+                (Mode::Armed, Indicator::Solid(Rgb::GREEN)),
+                (Mode::Precision, Indicator::Solid(Rgb::BLUE)),
+                (Mode::EStop, Indicator::Blink { color: Rgb::RED, period: Duration::from_millis(500) }),
+            ]),
+            player_leds: HashMap::from([
+                (Mode::Disarmed, 0b1_0001),
+                (Mode::Armed, 0b0_0100),
+                (Mode::Precision, 0b0_1110),
+                (Mode::EStop, 0b1_1111),
+            ]),
+            mode,
+        }
+    }
+    pub fn bind(mut self, mode: Mode, indicator: Indicator) -> ModeLights {
+        self.bindings.insert(mode, indicator);
+        self
+    }
+    /// Binds a player LED mask, as for `ControllerFeedback::set_player_leds`.
+    pub fn bind_player_leds(mut self, mode: Mode, mask: u8) -> ModeLights {
+        self.player_leds.insert(mode, mask);
+        self
+    }
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+    pub fn indicator(&self, mode: Mode) -> Indicator {
+        self.bindings[&mode]
+    }
+    pub fn player_leds(&self, mode: Mode) -> u8 {
+        self.player_leds[&mode]
+    }
+    /// Shows the current mode without signalling a change.
+    pub fn show_at(&self, feedback: &mut ControllerFeedback, now: Instant) {
+        feedback.set_indicator_at(self.indicator(self.mode), now);
+        feedback.set_player_leds(self.player_leds(self.mode));
+    }
+    /// Switches to `mode`, updating the lightbar and rumbling for the change:
+    /// arming, disarming and e-stop each have their own pattern, switching
+    /// between armed modes signals `Event::ModeChange`. Returns false if
+    /// already in `mode`.
+    pub fn set_at(&mut self, mode: Mode, feedback: &mut ControllerFeedback, now: Instant) -> bool {
+        if mode == self.mode {
+            return false;
+        }
+        let event = match mode {
+            Mode::EStop => Event::EStop,
+            Mode::Disarmed => Event::Disarmed,
+            _ if self.mode.is_armed() => Event::ModeChange,
+            _ => Event::Armed,
+        };
+        self.mode = mode;
+        self.show_at(feedback, now);
+        feedback.signal_at(event, now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_updates_indicator_once() {
+        let start = Instant::now();
+        let mut feedback = ControllerFeedback::new_at(start);
+        let mut lights = ModeLights::new(Mode::Disarmed).bind(Mode::Armed, Indicator::Solid(Rgb::new(0.0, 0.5, 0.0)));
+        lights.show_at(&mut feedback, start);
+        assert_eq!(feedback.indicator(), Indicator::Solid(Rgb::RED));
+        assert_eq!(feedback.player_leds(), 0b1_0001);
+        assert!(lights.set_at(Mode::Armed, &mut feedback, start));
+        assert!(!lights.set_at(Mode::Armed, &mut feedback, start));
+        assert_eq!(feedback.indicator(), Indicator::Solid(Rgb::new(0.0, 0.5, 0.0)));
+        lights.set_at(Mode::EStop, &mut feedback, start);
+        assert!(matches!(feedback.indicator(), Indicator::Blink { .. }));
+        assert_eq!(feedback.player_leds(), 0b1_1111);
+        assert!(!lights.mode().is_armed());
+    }
+
+    #[test]
+    fn switching_armed_modes_signals_mode_change() {
+        let start = Instant::now();
+        let mut feedback = ControllerFeedback::new_at(start);
+        let mut lights = ModeLights::new(Mode::Armed);
+        lights.show_at(&mut feedback, start);
+        feedback.tick_at(start);
+        assert!(lights.set_at(Mode::Precision, &mut feedback, start));
+        assert!(lights.mode().is_armed());
+        assert_eq!(feedback.indicator(), Indicator::Solid(Rgb::BLUE));
+        let items = feedback.tick_at(start).unwrap();
+        assert_eq!(items[0].intensity, Event::ModeChange.rumble().intensity);
+    }
+}
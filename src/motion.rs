@@ -0,0 +1,483 @@
+use std::time::{Duration, Instant};
+
+use crate::commands::PointCommand;
+use crate::joint::JointOutput;
+use crate::log::{pr_warn, Logger};
+use crate::persist::StartupPolicy;
+use crate::point_channel::{ChannelMap, PointChannel};
+use crate::sink::Sink;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileShape {
+    /// Constant acceleration up to `max_velocity`, then constant deceleration.
+    Trapezoidal,
+    /// Quintic (minimum-jerk) curve: acceleration starts and ends at zero.
+    SCurve,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionProfile {
+    pub shape: ProfileShape,
+    pub max_velocity: f32,
+    pub max_acceleration: f32,
+}
+
+impl MotionProfile {
+    pub fn trapezoidal(max_velocity: f32, max_acceleration: f32) -> MotionProfile {
+        MotionProfile {
+            shape: ProfileShape::Trapezoidal,
+            max_velocity: max_velocity.abs(),
+            max_acceleration: max_acceleration.abs(),
+        }
+    }
+    pub fn s_curve(max_velocity: f32, max_acceleration: f32) -> MotionProfile {
+        MotionProfile {
+            shape: ProfileShape::SCurve,
+            max_velocity: max_velocity.abs(),
+            max_acceleration: max_acceleration.abs(),
+        }
+    }
+}
+
+/// Quintic from the position, velocity and acceleration at `started_at` to
+/// rest at the target after `duration` seconds. Starting from the current
+/// motion keeps velocity and acceleration continuous across a retarget.
+struct Segment {
+    coefficients: [f32; 6],
+    started_at: Instant,
+    duration: f32,
+}
+
+impl Segment {
+    fn new(start: f32, velocity: f32, acceleration: f32, target: f32, duration: f32, started_at: Instant) -> Segment {
+        let (h, t) = (target - start, duration);
+        let coefficients = if t > 0.0 {
+            [
+                start,
+                velocity,
+                acceleration / 2.0,
+                (20.0 * h - 12.0 * velocity * t - 3.0 * acceleration * t * t) / (2.0 * t.powi(3)),
+                (-30.0 * h + 16.0 * velocity * t + 3.0 * acceleration * t * t) / (2.0 * t.powi(4)),
+                (12.0 * h - 6.0 * velocity * t - acceleration * t * t) / (2.0 * t.powi(5)),
+            ]
+        } else {
+            [target, 0.0, 0.0, 0.0, 0.0, 0.0]
+        };
+        Segment { coefficients, started_at, duration }
+    }
+    /// Position, velocity and acceleration `t` seconds into the segment.
+    fn sample(&self, t: f32) -> (f32, f32, f32) {
+        let c = &self.coefficients;
+        let position = c[0] + t * (c[1] + t * (c[2] + t * (c[3] + t * (c[4] + t * c[5]))));
+        let velocity = c[1] + t * (2.0 * c[2] + t * (3.0 * c[3] + t * (4.0 * c[4] + t * 5.0 * c[5])));
+        let acceleration = 2.0 * c[2] + t * (6.0 * c[3] + t * (12.0 * c[4] + t * 20.0 * c[5]));
+        (position, velocity, acceleration)
+    }
+}
+
+/// A position output that moves to its target along a motion profile.
+/// Call `tick_at` from a selector wall timer and publish every setpoint it
+/// returns; a new target replaces the move in progress.
+pub struct RampedOutput {
+    profile: MotionProfile,
+    position: f32,
+    velocity: f32,
+    acceleration: f32,
+    target: f32,
+    moving: bool,
+    segment: Option<Segment>,
+    last_update: Option<Instant>,
+}
+
+const MAX_TICK: Duration = Duration::from_millis(100);
+const SETTLE_EPSILON: f32 = 1e-3;
+
+impl RampedOutput {
+    pub fn new(initial: f32, profile: MotionProfile) -> RampedOutput {
+        RampedOutput {
+            profile,
+            position: initial,
+            velocity: 0.0,
+            acceleration: 0.0,
+            target: initial,
+            moving: false,
+            segment: None,
+            last_update: None,
+        }
+    }
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+    pub fn is_moving(&self) -> bool {
+        self.moving
+    }
+    /// Units per second.
+    pub fn velocity(&self) -> f32 {
+        self.velocity
+    }
+    pub fn setpoint<T: JointOutput>(&self) -> T {
+        T::from_position(self.position)
+    }
+    /// Moves immediately, dropping any move in progress.
+    pub fn jump_to(&mut self, position: f32) {
+        self.position = position;
+        self.target = position;
+        self.velocity = 0.0;
+        self.acceleration = 0.0;
+        self.moving = false;
+        self.segment = None;
+    }
+    pub fn set_target_at(&mut self, target: f32, now: Instant) {
+        if !target.is_finite() || (target == self.target && self.moving) {
+            return;
+        }
+        self.target = target;
+        if (target - self.position).abs() < SETTLE_EPSILON && self.velocity == 0.0 {
+            self.jump_to(target);
+            return;
+        }
+        if !self.moving {
+            self.last_update = Some(now);
+        }
+        self.moving = true;
+        if self.profile.shape == ProfileShape::SCurve {
+            // Leave room to brake from the current velocity as well.
+            let braking = if self.profile.max_acceleration > 0.0 {
+                self.velocity * self.velocity / self.profile.max_acceleration
+            } else {
+                0.0
+            };
+            let duration = s_curve_duration((target - self.position).abs() + braking, &self.profile);
+            self.segment = Some(Segment::new(self.position, self.velocity, self.acceleration, target, duration, now));
+        }
+    }
+    /// Advances the move and returns the new setpoint, or `None` once the
+    /// target has been reached and published.
+    pub fn tick_at(&mut self, now: Instant) -> Option<f32> {
+        if !self.moving {
+            return None;
+        }
+        let dt = match self.last_update {
+            Some(last) => now.saturating_duration_since(last).min(MAX_TICK).as_secs_f32(),
+            None => 0.0,
+        };
+        self.last_update = Some(now);
+        match self.profile.shape {
+            ProfileShape::Trapezoidal => self.step_trapezoidal(dt),
+            ProfileShape::SCurve => self.step_s_curve(now),
+        }
+        Some(self.position)
+    }
+    fn step_trapezoidal(&mut self, dt: f32) {
+        let remaining = self.target - self.position;
+        let max_acceleration = self.profile.max_acceleration;
+        let desired = remaining.signum()
+            * self
+                .profile
+                .max_velocity
+                .min((2.0 * max_acceleration * remaining.abs()).sqrt());
+        let dv = (desired - self.velocity).clamp(-max_acceleration * dt, max_acceleration * dt);
+        self.velocity += dv;
+        let next = self.position + self.velocity * dt;
+        let overshot = (self.target - next).signum() != remaining.signum();
+        if overshot || ((self.target - next).abs() < SETTLE_EPSILON && self.velocity.abs() <= max_acceleration * dt) {
+            self.jump_to(self.target);
+        } else {
+            self.position = next;
+        }
+    }
+    fn step_s_curve(&mut self, now: Instant) {
+        let Some(segment) = &self.segment else {
+            self.jump_to(self.target);
+            return;
+        };
+        let elapsed = now.saturating_duration_since(segment.started_at).as_secs_f32();
+        if segment.duration <= 0.0 || elapsed >= segment.duration {
+            self.jump_to(self.target);
+            return;
+        }
+        (self.position, self.velocity, self.acceleration) = segment.sample(elapsed);
+    }
+}
+
+/// Duration of a minimum-jerk move from rest so that neither the peak velocity
+/// (15/8 d/T) nor the peak acceleration (10/sqrt(3) d/T^2) exceeds the profile.
+fn s_curve_duration(distance: f32, profile: &MotionProfile) -> f32 {
+    let by_velocity = if profile.max_velocity > 0.0 {
+        15.0 * distance / (8.0 * profile.max_velocity)
+    } else {
+        0.0
+    };
+    let by_acceleration = if profile.max_acceleration > 0.0 {
+        (10.0 / 3f32.sqrt() * distance / profile.max_acceleration).sqrt()
+    } else {
+        0.0
+    };
+    by_velocity.max(by_acceleration)
+}
+
+/// Ramped outputs for several `PointDrive` channels of one robot.
+pub struct ChannelRamps {
+    ramps: Vec<(PointChannel, RampedOutput)>,
+}
+
+impl ChannelRamps {
+    pub fn new() -> ChannelRamps {
+        ChannelRamps { ramps: Vec::new() }
+    }
+    pub fn with(mut self, channel: PointChannel, initial: f32, profile: MotionProfile) -> ChannelRamps {
+        self.ramps.retain(|(c, _)| *c != channel);
+        self.ramps.push((channel, RampedOutput::new(initial, profile)));
+        self
+    }
+    pub fn get(&self, channel: PointChannel) -> Option<&RampedOutput> {
+        self.ramps.iter().find(|(c, _)| *c == channel).map(|(_, ramp)| ramp)
+    }
+    pub fn get_mut(&mut self, channel: PointChannel) -> Option<&mut RampedOutput> {
+        self.ramps.iter_mut().find(|(c, _)| *c == channel).map(|(_, ramp)| ramp)
+    }
+    /// Returns `false` if the channel has no ramp.
+    pub fn set_target_at(&mut self, channel: PointChannel, target: f32, now: Instant) -> bool {
+        match self.get_mut(channel) {
+            Some(ramp) => {
+                ramp.set_target_at(target, now);
+                true
+            }
+            None => false,
+        }
+    }
+    pub fn is_moving(&self) -> bool {
+        self.ramps.iter().any(|(_, ramp)| ramp.is_moving())
+    }
+    /// Starts every `restored` channel from the value in `channels` instead of
+    /// its initial position. With `StartupPolicy::Home` those channels then
+    /// ramp back to their initial position, starting at `now`.
+    pub fn start_from_at(&mut self, channels: &ChannelMap, restored: &[PointChannel], policy: StartupPolicy, now: Instant) {
+        for (channel, ramp) in self.ramps.iter_mut() {
+            if !restored.contains(channel) {
+                continue;
+            }
+            let home = ramp.target();
+            ramp.jump_to(channels.get(*channel) as f32);
+            if policy == StartupPolicy::Home {
+                ramp.set_target_at(home, now);
+            }
+        }
+    }
+    /// Setpoints of every channel that moved since the last tick.
+    pub fn tick_at(&mut self, now: Instant) -> Vec<(PointChannel, i16)> {
+        self.ramps
+            .iter_mut()
+            .filter_map(|(channel, ramp)| ramp.tick_at(now).map(|_| (*channel, ramp.setpoint::<i16>())))
+            .collect()
+    }
+}
+
+impl Default for ChannelRamps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub const RAMP_PERIOD: Duration = Duration::from_millis(20);
+
+/// Advances `ramps` and publishes the moved channels through `channels`.
+/// Setpoints are clamped to the declared ranges: an S-curve retargeted at
+/// speed can swing past its old target, and one bad value would otherwise
+/// hold every channel back for the tick. Also gives `channels` its chance
+/// to save the actuator state.
+pub fn publish_ramps_at(
+    ramps: &mut ChannelRamps,
+    channels: &mut ChannelMap,
+    publisher: &dyn Sink<PointCommand>,
+    logger: &Logger,
+    now: Instant,
+) {
+    channels.save_state_at(now);
+    if let Some(e) = channels.take_state_error() {
+        pr_warn!(logger, "could not save actuator state: {}", e);
+    }
+    let writes: Vec<(PointChannel, i16)> = ramps
+        .tick_at(now)
+        .into_iter()
+        .map(|(channel, value)| match channels.range(channel) {
+            Some(range) => (channel, value.clamp(*range.start(), *range.end())),
+            None => (channel, value),
+        })
+        .collect();
+    if writes.is_empty() {
+        return;
+    }
+    match channels.write_all(&writes) {
+        Ok(()) => publisher.send(channels.command()),
+        Err(e) => pr_warn!(logger, "{}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point_channel::ChannelKind;
+
+    fn run(ramp: &mut RampedOutput, start: Instant, step_ms: u64, steps: u64) -> Vec<f32> {
+        (1..=steps)
+            .filter_map(|i| ramp.tick_at(start + Duration::from_millis(i * step_ms)))
+            .collect()
+    }
+
+    fn check_limits(points: &[f32], step: f32, profile: &MotionProfile) {
+        let velocities: Vec<f32> = points.windows(2).map(|w| (w[1] - w[0]) / step).collect();
+        for v in &velocities {
+            assert!(v.abs() <= profile.max_velocity * 1.01, "velocity {}", v);
+        }
+        for w in velocities.windows(2) {
+            let a = (w[1] - w[0]) / step;
+            assert!(a.abs() <= profile.max_acceleration * 1.05, "acceleration {}", a);
+        }
+    }
+
+    #[test]
+    fn trapezoidal_reaches_target_within_limits() {
+        let start = Instant::now();
+        let profile = MotionProfile::trapezoidal(100.0, 400.0);
+        let mut ramp = RampedOutput::new(25.0, profile);
+        ramp.set_target_at(125.0, start);
+        let points = run(&mut ramp, start, 10, 300);
+        assert_eq!(*points.last().unwrap(), 125.0);
+        assert!(!ramp.is_moving());
+        assert!(points.iter().all(|p| (25.0..=125.0).contains(p)));
+        check_limits(&points[..points.len() - 1], 0.01, &profile);
+        assert_eq!(ramp.tick_at(start + Duration::from_secs(10)), None);
+    }
+
+    #[test]
+    fn s_curve_reaches_target_within_limits() {
+        let start = Instant::now();
+        let profile = MotionProfile::s_curve(100.0, 400.0);
+        let mut ramp = RampedOutput::new(125.0, profile);
+        ramp.set_target_at(25.0, start);
+        let points = run(&mut ramp, start, 10, 300);
+        assert_eq!(*points.last().unwrap(), 25.0);
+        check_limits(&points, 0.01, &profile);
+    }
+
+    #[test]
+    fn new_target_replaces_move_in_progress() {
+        let start = Instant::now();
+        let mut ramp = RampedOutput::new(25.0, MotionProfile::trapezoidal(100.0, 400.0));
+        ramp.set_target_at(125.0, start);
+        run(&mut ramp, start, 10, 20);
+        let midway = ramp.position();
+        assert!(midway > 25.0 && midway < 125.0);
+        ramp.set_target_at(80.0, start + Duration::from_millis(200));
+        let points = run(&mut ramp, start + Duration::from_millis(200), 10, 300);
+        assert_eq!(*points.last().unwrap(), 80.0);
+        assert!(points.iter().all(|p| *p <= 125.0));
+    }
+
+    #[test]
+    fn target_at_current_position_settles_immediately() {
+        let start = Instant::now();
+        let mut ramp = RampedOutput::new(80.0, MotionProfile::s_curve(100.0, 400.0));
+        ramp.set_target_at(80.0, start);
+        assert!(!ramp.is_moving());
+        ramp.set_target_at(f32::NAN, start);
+        assert_eq!(ramp.target(), 80.0);
+    }
+
+    #[test]
+    fn s_curve_retarget_keeps_velocity_continuous() {
+        let start = Instant::now();
+        let profile = MotionProfile::s_curve(100.0, 400.0);
+        let mut ramp = RampedOutput::new(25.0, profile);
+        ramp.set_target_at(125.0, start);
+        run(&mut ramp, start, 10, 30);
+        let before = ramp.velocity();
+        assert!(before > 20.0, "velocity {}", before);
+        let retarget = start + Duration::from_millis(300);
+        ramp.set_target_at(60.0, retarget);
+        assert_eq!(ramp.velocity(), before);
+        ramp.tick_at(retarget + Duration::from_millis(10));
+        let after = ramp.velocity();
+        assert!((after - before).abs() <= profile.max_acceleration * 0.01 * 1.5, "{} -> {}", before, after);
+        let points = run(&mut ramp, retarget + Duration::from_millis(10), 10, 500);
+        assert_eq!(*points.last().unwrap(), 60.0);
+        assert!(points.iter().all(|p| (25.0..=125.0).contains(p)));
+    }
+
+    #[test]
+    fn retarget_at_speed_stays_in_range() {
+        #[derive(Default)]
+        struct Sent(std::cell::RefCell<Vec<PointCommand>>);
+        impl Sink<PointCommand> for Sent {
+            fn send(&self, command: &PointCommand) {
+                self.0.borrow_mut().push(*command);
+            }
+        }
+
+        let start = Instant::now();
+        let profile = MotionProfile::s_curve(300.0, 400.0);
+        let mut channels = ChannelMap::new()
+            .declare(PointChannel::Md2, ChannelKind::Servo("wrist"), 25..=125)
+            .declare(PointChannel::Md3, ChannelKind::Servo("claw"), 25..=125);
+        let mut ramps = ChannelRamps::new()
+            .with(PointChannel::Md2, 25.0, profile)
+            .with(PointChannel::Md3, 25.0, MotionProfile::trapezoidal(50.0, 400.0));
+        ramps.set_target_at(PointChannel::Md2, 125.0, start);
+        ramps.set_target_at(PointChannel::Md3, 125.0, start);
+        let sent = Sent::default();
+        let logger = Logger::new("p9n_interface_2024");
+        let mut now = start;
+        for i in 1..=300 {
+            now = start + Duration::from_millis(10 * i);
+            if i == 60 {
+                ramps.set_target_at(PointChannel::Md2, 124.0, now);
+            }
+            publish_ramps_at(&mut ramps, &mut channels, &sent, &logger, now);
+        }
+        assert!(!ramps.is_moving(), "still moving at {:?}", now - start);
+        let sent = sent.0.borrow();
+        assert!(sent.iter().all(|c| (25..=125).contains(&c.md2) && (25..=125).contains(&c.md3)));
+        // The overshoot is held at the limit instead of dropping the tick,
+        // so md3 keeps its steady half a step per tick throughout.
+        assert!(sent.iter().any(|c| c.md2 == 125));
+        let claw: Vec<i8> = sent.iter().map(|c| c.md3).collect();
+        assert!(claw.windows(2).all(|w| (0..=1).contains(&(w[1] - w[0]))));
+        assert_eq!((sent.last().unwrap().md2, sent.last().unwrap().md3), (124, 125));
+    }
+
+    #[test]
+    fn ramp_saves_state_at_most_once_per_interval() {
+        use crate::persist::{StateFile, SAVE_INTERVAL};
+
+        struct Discard;
+        impl Sink<PointCommand> for Discard {
+            fn send(&self, _: &PointCommand) {}
+        }
+
+        let path = std::env::temp_dir().join(format!("p9n_ramp_saves_{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let start = Instant::now();
+        let mut channels = ChannelMap::new()
+            .declare(PointChannel::Md2, ChannelKind::Servo("wrist"), 25..=125)
+            .persist_to(StateFile::new(&path));
+        let mut ramps = ChannelRamps::new().with(PointChannel::Md2, 25.0, MotionProfile::trapezoidal(50.0, 400.0));
+        ramps.set_target_at(PointChannel::Md2, 125.0, start);
+        let logger = Logger::new("p9n_interface_2024");
+        let ticks = 200;
+        for i in 1..=ticks {
+            let now = start + RAMP_PERIOD * i;
+            publish_ramps_at(&mut ramps, &mut channels, &Discard, &logger, now);
+        }
+        // The move takes about 2.1 s of the 4 s run; it changed the value on
+        // ~100 ticks but the file is written once per interval at most.
+        let run = RAMP_PERIOD * ticks;
+        let writes = channels.state_file().unwrap().writes();
+        assert!(writes as u32 <= run.as_millis() as u32 / SAVE_INTERVAL.as_millis() as u32 + 1, "{} writes", writes);
+        assert_eq!(StateFile::new(&path).load().unwrap(), vec![("md2".to_string(), 125)]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
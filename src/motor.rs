@@ -0,0 +1,183 @@
+use std::time::{Duration, Instant};
+
+use crate::commands::MdCommand;
+
+pub const SLEW_PERIOD: Duration = Duration::from_millis(20);
+const MAX_TICK: Duration = Duration::from_millis(100);
+
+/// One MD driver output whose power changes are limited to `acceleration`
+/// (magnitude rising) and `deceleration` (magnitude falling), both in power
+/// units per second. Power is signed; negative power is sent with `phase`
+/// set. `emergency_stop` bypasses the limit. A rate that is zero, negative
+/// or not finite means no limit in that direction.
+pub struct SlewLimitedMotor {
+    address: u8,
+    mode: u8,
+    acceleration: f32,
+    deceleration: f32,
+    power: f32,
+    target: f32,
+    last_update: Option<Instant>,
+}
+
+impl SlewLimitedMotor {
+    pub fn new(address: u8, mode: u8, acceleration: f32, deceleration: f32) -> SlewLimitedMotor {
+        SlewLimitedMotor {
+            address,
+            mode,
+            acceleration: rate_limit(acceleration),
+            deceleration: rate_limit(deceleration),
+            power: 0.0,
+            target: 0.0,
+            last_update: None,
+        }
+    }
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+    pub fn power(&self) -> i32 {
+        self.power.round() as i32
+    }
+    pub fn target(&self) -> i32 {
+        self.target.round() as i32
+    }
+    pub fn is_ramping(&self) -> bool {
+        self.power != self.target
+    }
+    pub fn set_target_at(&mut self, power: i32, now: Instant) {
+        if !self.is_ramping() {
+            self.last_update = Some(now);
+        }
+        self.target = power.clamp(-(u16::MAX as i32), u16::MAX as i32) as f32;
+    }
+    /// Stops the motor at once and returns the command to send immediately.
+    pub fn emergency_stop(&mut self) -> MdCommand {
+        self.power = 0.0;
+        self.target = 0.0;
+        self.last_update = None;
+        self.command()
+    }
+    /// Steps the power towards the target. Returns a command for every
+    /// intermediate and the final power, then `None` while idle. A tick with
+    /// no time passed since the target was set sends nothing.
+    pub fn tick_at(&mut self, now: Instant) -> Option<MdCommand> {
+        if !self.is_ramping() {
+            return None;
+        }
+        let dt = match self.last_update {
+            Some(last) => now.saturating_duration_since(last).min(MAX_TICK).as_secs_f32(),
+            None => 0.0,
+        };
+        self.last_update = Some(now);
+        if dt <= 0.0 {
+            return None;
+        }
+        let mut budget = dt;
+        // Slow down towards zero first when the target is on the other side.
+        if self.power != 0.0 && (self.target.signum() != self.power.signum() || self.target.abs() < self.power.abs()) {
+            let floor = if self.target.signum() == self.power.signum() { self.target.abs() } else { 0.0 };
+            let step = self.deceleration * budget;
+            let magnitude = self.power.abs() - floor;
+            if step >= magnitude {
+                budget -= magnitude / self.deceleration;
+                self.power = floor * self.power.signum();
+            } else {
+                self.power -= step * self.power.signum();
+                budget = 0.0;
+            }
+        }
+        if budget > 0.0 && self.target.abs() > self.power.abs() {
+            let step = self.acceleration * budget;
+            if step >= self.target.abs() - self.power.abs() {
+                self.power = self.target;
+            } else {
+                self.power = self.target.signum() * (self.power.abs() + step);
+            }
+        }
+        Some(self.command())
+    }
+    pub fn command(&self) -> MdCommand {
+        MdCommand {
+            address: self.address,
+            mode: self.mode,
+            phase: self.power < 0.0,
+            power: self.power.abs().round() as u16,
+        }
+    }
+}
+
+fn rate_limit(rate: f32) -> f32 {
+    if rate.is_finite() && rate != 0.0 {
+        rate.abs()
+    } else {
+        f32::INFINITY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn powers(motor: &mut SlewLimitedMotor, start: Instant, steps: u64) -> Vec<i32> {
+        (1..=steps)
+            .filter_map(|i| motor.tick_at(start + Duration::from_millis(i * 20)).map(|_| motor.power()))
+            .collect()
+    }
+
+    #[test]
+    fn ramps_up_and_down_at_configured_rates() {
+        let start = Instant::now();
+        let mut motor = SlewLimitedMotor::new(0x05, 2, 5000.0, 10000.0);
+        motor.set_target_at(1000, start);
+        assert_eq!(powers(&mut motor, start, 20), vec![100, 200, 300, 400, 500, 600, 700, 800, 900, 1000]);
+        let later = start + Duration::from_secs(1);
+        motor.set_target_at(0, later);
+        assert_eq!(powers(&mut motor, later, 20), vec![800, 600, 400, 200, 0]);
+    }
+
+    #[test]
+    fn reverses_through_zero() {
+        let start = Instant::now();
+        let mut motor = SlewLimitedMotor::new(0x05, 2, 5000.0, 20000.0);
+        motor.set_target_at(200, start);
+        powers(&mut motor, start, 10);
+        let later = start + Duration::from_secs(1);
+        motor.set_target_at(-200, later);
+        // 20 ms: 10 ms to stop from 200, then 10 ms accelerating the other way.
+        assert_eq!(powers(&mut motor, later, 10), vec![-50, -150, -200]);
+        assert!(motor.command().phase);
+        assert_eq!(motor.command().power, 200);
+    }
+
+    #[test]
+    fn emergency_stop_is_immediate() {
+        let start = Instant::now();
+        let mut motor = SlewLimitedMotor::new(0x05, 2, 5000.0, 100.0);
+        motor.set_target_at(1000, start);
+        powers(&mut motor, start, 20);
+        let msg = motor.emergency_stop();
+        assert_eq!(msg.power, 0);
+        assert!(!motor.is_ramping());
+        assert!(motor.tick_at(start + Duration::from_secs(2)).is_none());
+    }
+
+    #[test]
+    fn nothing_sent_before_time_passes() {
+        let start = Instant::now();
+        let mut motor = SlewLimitedMotor::new(0x05, 2, 5000.0, 10000.0);
+        motor.set_target_at(1000, start);
+        assert_eq!(motor.tick_at(start), None);
+        assert_eq!(powers(&mut motor, start, 1), vec![100]);
+    }
+
+    #[test]
+    fn zero_rate_means_unlimited() {
+        let start = Instant::now();
+        let mut motor = SlewLimitedMotor::new(0x05, 2, 0.0, f32::NAN);
+        motor.set_target_at(1000, start);
+        assert_eq!(powers(&mut motor, start, 5), vec![1000]);
+        let later = start + Duration::from_secs(1);
+        motor.set_target_at(-300, later);
+        assert_eq!(powers(&mut motor, later, 5), vec![-300]);
+    }
+}
@@ -0,0 +1,204 @@
+use std::time::{Duration, Instant};
+
+use crate::air_budget::{AirBudget, AirWarning};
+use crate::commands::{MdCommand, SdCommand};
+use crate::interlock::{Blocked, Interlocks, Output};
+use crate::motor::SlewLimitedMotor;
+use crate::solenoid::SolenoidBank;
+
+/// The MD/SD side of a robot: its solenoid banks and slew-limited motors
+/// behind one set of interlocks. Every method returns the commands to send.
+pub struct OutputLayer {
+    solenoids: Vec<SolenoidBank>,
+    motors: Vec<SlewLimitedMotor>,
+    interlocks: Interlocks,
+    air_budget: Option<AirBudget>,
+    air_warnings: Vec<AirWarning>,
+}
+
+impl OutputLayer {
+    pub fn new() -> OutputLayer {
+        OutputLayer {
+            solenoids: Vec::new(),
+            motors: Vec::new(),
+            interlocks: Interlocks::new(),
+            air_budget: None,
+            air_warnings: Vec::new(),
+        }
+    }
+    pub fn with_solenoids(mut self, address: u8) -> OutputLayer {
+        self.solenoids.retain(|bank| bank.address() != address);
+        self.solenoids.push(SolenoidBank::new(address));
+        self
+    }
+    pub fn with_motor(mut self, motor: SlewLimitedMotor) -> OutputLayer {
+        self.motors.retain(|m| m.address() != motor.address());
+        self.motors.push(motor);
+        self
+    }
+    pub fn with_interlocks(mut self, interlocks: Interlocks) -> OutputLayer {
+        self.interlocks = interlocks;
+        self
+    }
+    /// Counts every solenoid message this layer emits against `air_budget`.
+    pub fn with_air_budget(mut self, air_budget: AirBudget) -> OutputLayer {
+        self.air_budget = Some(air_budget);
+        self
+    }
+    pub fn air_budget(&self) -> Option<&AirBudget> {
+        self.air_budget.as_ref()
+    }
+    pub fn air_budget_mut(&mut self) -> Option<&mut AirBudget> {
+        self.air_budget.as_mut()
+    }
+    /// Warnings raised since the last call.
+    pub fn take_air_warnings(&mut self) -> Vec<AirWarning> {
+        std::mem::take(&mut self.air_warnings)
+    }
+    pub fn is_on(&self, output: Output) -> bool {
+        match output {
+            Output::Solenoid { address, port } => self.bank(address).is_some_and(|bank| bank.is_on(port)),
+            Output::Motor { address } => self.motor(address).is_some_and(is_running),
+        }
+    }
+    pub fn set_solenoid(&mut self, address: u8, port: u8, on: bool) -> Result<Option<SdCommand>, Blocked> {
+        let output = Output::Solenoid { address, port };
+        self.interlocks.check(output, on)?;
+        let Some(bank) = self.bank_mut(address) else {
+            return Ok(None);
+        };
+        let msg = if on { bank.on(port) } else { bank.off(port) };
+        self.interlocks.set_state(output, on);
+        Ok(self.count(msg))
+    }
+    pub fn toggle_solenoid(&mut self, address: u8, port: u8) -> Result<Option<SdCommand>, Blocked> {
+        let on = !self.is_on(Output::Solenoid { address, port });
+        self.set_solenoid(address, port, on)
+    }
+    /// Turns the port on and lets `tick_at` turn it off after `duration`.
+    pub fn pulse_solenoid_at(
+        &mut self,
+        address: u8,
+        port: u8,
+        duration: Duration,
+        now: Instant,
+    ) -> Result<Option<SdCommand>, Blocked> {
+        let output = Output::Solenoid { address, port };
+        self.interlocks.check(output, true)?;
+        let Some(bank) = self.bank_mut(address) else {
+            return Ok(None);
+        };
+        let msg = bank.pulse_at(port, duration, now);
+        self.interlocks.set_state(output, true);
+        Ok(self.count(msg))
+    }
+    pub fn set_motor_at(&mut self, address: u8, power: i32, now: Instant) -> Result<(), Blocked> {
+        let output = Output::Motor { address };
+        self.interlocks.check(output, power != 0)?;
+        if let Some(motor) = self.motor_mut(address) {
+            motor.set_target_at(power, now);
+            let running = is_running(motor);
+            self.interlocks.set_state(output, running);
+        }
+        Ok(())
+    }
+    /// Stops every motor at once, bypassing slew limits and interlocks.
+    pub fn emergency_stop(&mut self) -> Vec<MdCommand> {
+        let mut msgs = Vec::new();
+        for motor in self.motors.iter_mut() {
+            msgs.push(motor.emergency_stop());
+            self.interlocks.set_state(Output::Motor { address: motor.address() }, false);
+        }
+        msgs
+    }
+    /// Advances pulses and motor ramps. Call from a selector wall timer.
+    pub fn tick_at(&mut self, now: Instant) -> (Vec<SdCommand>, Vec<MdCommand>) {
+        let mut sd_msgs = Vec::new();
+        for bank in self.solenoids.iter_mut() {
+            for msg in bank.tick_at(now) {
+                self.interlocks.set_state(Output::Solenoid { address: msg.address, port: msg.port }, false);
+                if let Some(air_budget) = self.air_budget.as_mut() {
+                    air_budget.record(&msg);
+                }
+                sd_msgs.push(msg);
+            }
+        }
+        let mut md_msgs = Vec::new();
+        for motor in self.motors.iter_mut() {
+            if let Some(msg) = motor.tick_at(now) {
+                self.interlocks.set_state(Output::Motor { address: motor.address() }, is_running(motor));
+                md_msgs.push(msg);
+            }
+        }
+        (sd_msgs, md_msgs)
+    }
+    fn count(&mut self, msg: Option<SdCommand>) -> Option<SdCommand> {
+        if let (Some(msg), Some(air_budget)) = (msg.as_ref(), self.air_budget.as_mut()) {
+            self.air_warnings.extend(air_budget.record(msg));
+        }
+        msg
+    }
+    fn bank(&self, address: u8) -> Option<&SolenoidBank> {
+        self.solenoids.iter().find(|bank| bank.address() == address)
+    }
+    fn bank_mut(&mut self, address: u8) -> Option<&mut SolenoidBank> {
+        self.solenoids.iter_mut().find(|bank| bank.address() == address)
+    }
+    fn motor(&self, address: u8) -> Option<&SlewLimitedMotor> {
+        self.motors.iter().find(|motor| motor.address() == address)
+    }
+    fn motor_mut(&mut self, address: u8) -> Option<&mut SlewLimitedMotor> {
+        self.motors.iter_mut().find(|motor| motor.address() == address)
+    }
+}
+
+/// A motor counts as on for the interlocks until it has actually spun down,
+/// not as soon as its target is zero.
+fn is_running(motor: &SlewLimitedMotor) -> bool {
+    motor.power() != 0 || motor.target() != 0
+}
+
+impl Default for OutputLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interlock::Condition;
+
+    const LOCK: Output = Output::Solenoid { address: 0x05, port: 0 };
+    const MOTOR: Output = Output::Motor { address: 0x06 };
+
+    #[test]
+    fn lock_waits_for_motor_to_spin_down() {
+        let mut outputs = OutputLayer::new()
+            .with_solenoids(0x05)
+            .with_motor(SlewLimitedMotor::new(0x06, 0, 1000.0, 1000.0))
+            .with_interlocks(Interlocks::new().rule("no drive while locked", MOTOR, Condition::Off(LOCK)));
+        let start = Instant::now();
+        outputs.set_motor_at(0x06, 500, start).unwrap();
+        outputs.tick_at(start + Duration::from_millis(100));
+        outputs.tick_at(start + Duration::from_millis(200));
+        outputs.tick_at(start + Duration::from_millis(300));
+        outputs.tick_at(start + Duration::from_millis(400));
+        outputs.tick_at(start + Duration::from_millis(500));
+        outputs.tick_at(start + Duration::from_millis(600));
+        assert_eq!(outputs.motor(0x06).unwrap().power(), 500);
+
+        let stop = start + Duration::from_millis(600);
+        outputs.set_motor_at(0x06, 0, stop).unwrap();
+        assert!(outputs.is_on(MOTOR));
+        assert!(outputs.set_solenoid(0x05, 0, true).is_err());
+        outputs.tick_at(stop + Duration::from_millis(100));
+        assert_eq!(outputs.motor(0x06).unwrap().power(), 400);
+        assert!(outputs.set_solenoid(0x05, 0, true).is_err());
+        for step in 2..=5 {
+            outputs.tick_at(stop + Duration::from_millis(100 * step));
+        }
+        assert!(!outputs.is_on(MOTOR));
+        assert!(outputs.set_solenoid(0x05, 0, true).is_ok());
+    }
+}
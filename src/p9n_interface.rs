@@ -1,64 +1,108 @@
+#[cfg(feature = "ros")]
 use safe_drive::msg::common_interfaces::sensor_msgs;
+use crate::controller::ControllerState;
+use crate::dualsense_sensors::{ImuReading, TouchPad, TouchPoint};
 use crate::ps5_dualsense::AXES_DUALSENSE;
 use crate::ps5_dualsense::BUTTONS_DUALSENSE;
 
 pub struct PlaystationInterface {
-    msg: sensor_msgs::msg::Joy,
+    state: ControllerState,
 }
 
 impl PlaystationInterface {
+    #[cfg(feature = "ros")]
     pub fn new(_msg: sensor_msgs::msg::Joy) -> PlaystationInterface {
-        PlaystationInterface { msg: _msg, }
+        PlaystationInterface { state: ControllerState::from_joy(&_msg) }
     }
+    pub fn from_state(state: ControllerState) -> PlaystationInterface {
+        PlaystationInterface { state }
+    }
+    #[cfg(feature = "ros")]
     pub fn set_joy_msg(&mut self, _msg: sensor_msgs::msg::Joy){
-        self.msg = _msg;
+        self.state.set_joy(&_msg);
+    }
+    #[cfg(feature = "ros")]
+    pub fn set_imu_msg(&mut self, _msg: &sensor_msgs::msg::Imu) {
+        self.state.set_imu(_msg);
+    }
+    #[cfg(feature = "ros")]
+    pub fn set_touch_msg(&mut self, _msg: &sensor_msgs::msg::Joy) {
+        self.state.set_touch(_msg);
+    }
+    /// Replaces the whole snapshot, e.g. with one from an `InputSource`.
+    pub fn set_state(&mut self, state: ControllerState) {
+        self.state = state;
+    }
+    pub fn state(&self) -> &ControllerState {
+        &self.state
     }
     pub fn pressed_start(&self) -> bool {
-        self.msg.buttons.as_slice()[BUTTONS_DUALSENSE::START] == 1
+        self.state.button(BUTTONS_DUALSENSE::START)
     }
     pub fn pressed_select(&self) -> bool {
-        self.msg.buttons.as_slice()[BUTTONS_DUALSENSE::SELECT] == 1
+        self.state.button(BUTTONS_DUALSENSE::SELECT)
+    }
+    pub fn pressed_ps(&self) -> bool {
+        self.state.button(BUTTONS_DUALSENSE::PS)
     }
     pub fn pressed_circle(&self) -> bool {
-        self.msg.buttons.as_slice()[BUTTONS_DUALSENSE::CIRCLE] == 1
+        self.state.button(BUTTONS_DUALSENSE::CIRCLE)
     }
     pub fn pressed_triangle(&self) -> bool {
-        self.msg.buttons.as_slice()[BUTTONS_DUALSENSE::TRIANGLE] == 1
+        self.state.button(BUTTONS_DUALSENSE::TRIANGLE)
     }
     pub fn pressed_square(&self) -> bool {
-        self.msg.buttons.as_slice()[BUTTONS_DUALSENSE::SQUARE] == 1
+        self.state.button(BUTTONS_DUALSENSE::SQUARE)
     }
     pub fn pressed_cross(&self) -> bool {
-        self.msg.buttons.as_slice()[BUTTONS_DUALSENSE::CROSS] == 1
+        self.state.button(BUTTONS_DUALSENSE::CROSS)
     }
     pub fn pressed_l1(&self) -> bool {
-        self.msg.buttons.as_slice()[BUTTONS_DUALSENSE::L1] == 1
+        self.state.button(BUTTONS_DUALSENSE::L1)
     }
     pub fn pressed_r1(&self) -> bool {
-        self.msg.buttons.as_slice()[BUTTONS_DUALSENSE::R1] == 1
+        self.state.button(BUTTONS_DUALSENSE::R1)
     }
     pub fn pressed_l2(&self) -> bool {
-        self.msg.buttons.as_slice()[BUTTONS_DUALSENSE::L2] == 1
+        self.state.button(BUTTONS_DUALSENSE::L2)
     }
     pub fn pressed_r2(&self) -> bool {
-        self.msg.buttons.as_slice()[BUTTONS_DUALSENSE::R2] == 1
+        self.state.button(BUTTONS_DUALSENSE::R2)
     }
     pub fn pressed_dpad_left(&self) -> bool {
-         self.msg.axes.as_slice()[AXES_DUALSENSE::DPAD_X] > 0.0
+         self.state.axis(AXES_DUALSENSE::DPAD_X) > 0.0
     }
     pub fn pressed_dpad_right(&self) -> bool {
-        self.msg.axes.as_slice()[AXES_DUALSENSE::DPAD_X] < 0.0
+        self.state.axis(AXES_DUALSENSE::DPAD_X) < 0.0
     }
     pub fn pressed_dpad_up(&self) -> bool {
-        self.msg.axes.as_slice()[AXES_DUALSENSE::DPAD_Y] > 0.0
+        self.state.axis(AXES_DUALSENSE::DPAD_Y) > 0.0
     }
     pub fn pressed_dpad_down(&self) -> bool {
-        self.msg.axes.as_slice()[AXES_DUALSENSE::DPAD_Y] < 0.0
+        self.state.axis(AXES_DUALSENSE::DPAD_Y) < 0.0
     }
     pub fn pressed_l2_analog(&self) -> f32 {
-        self.msg.axes.as_slice()[AXES_DUALSENSE::L2]
+        self.state.axis(AXES_DUALSENSE::L2)
     }
     pub fn pressed_r2_analog(&self) -> f32 {
-        self.msg.axes.as_slice()[AXES_DUALSENSE::R2]
+        self.state.axis(AXES_DUALSENSE::R2)
+    }
+    /// Latest IMU sample, if an IMU topic is subscribed.
+    pub fn imu(&self) -> Option<&ImuReading> {
+        self.state.imu.as_ref()
+    }
+    /// Roll and pitch in radians.
+    pub fn tilt(&self) -> Option<(f64, f64)> {
+        self.state.imu.map(|imu| imu.tilt())
+    }
+    /// Angular rate around x, y and z in rad/s.
+    pub fn angular_rate(&self) -> Option<[f64; 3]> {
+        self.state.imu.map(|imu| imu.angular_velocity)
+    }
+    pub fn touch_points(&self) -> impl Iterator<Item = TouchPoint> + '_ {
+        self.state.touchpad.points.iter().flatten().copied()
+    }
+    pub fn touchpad(&self) -> &TouchPad {
+        &self.state.touchpad
     }
 }
\ No newline at end of file
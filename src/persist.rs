@@ -0,0 +1,186 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Writes `contents` to a temporary file next to `path` and renames it into
+/// place, so a crash never leaves a half-written file behind. The data and
+/// the rename are both synced, so a power cut leaves either the old or the
+/// new file.
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp, path)?;
+    File::open(parent)?.sync_all()
+}
+
+/// Environment variable naming the state file. Persistence is off when unset.
+pub const STATE_FILE_ENV: &str = "P9N_STATE_FILE";
+/// Environment variable holding the `StartupPolicy` (`resume` or `home`).
+pub const STARTUP_POLICY_ENV: &str = "P9N_STARTUP_POLICY";
+
+/// What a node does with the actuator state it finds on startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupPolicy {
+    /// Keep the actuators where the previous run left them.
+    Resume,
+    /// Move from the stored positions back to the home positions.
+    Home,
+}
+
+impl FromStr for StartupPolicy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "resume" => Ok(StartupPolicy::Resume),
+            "home" => Ok(StartupPolicy::Home),
+            other => Err(format!("unknown startup policy '{}', expected resume or home", other)),
+        }
+    }
+}
+
+impl StartupPolicy {
+    /// Reads `STARTUP_POLICY_ENV`, defaulting to `Resume` when unset.
+    pub fn from_env() -> Result<StartupPolicy, String> {
+        match std::env::var(STARTUP_POLICY_ENV) {
+            Ok(value) => value.parse(),
+            Err(_) => Ok(StartupPolicy::Resume),
+        }
+    }
+}
+
+/// Shortest time between two `save_at` writes. Each write syncs twice, which
+/// can take tens of milliseconds on an SD card, so a ramp moving a servo on
+/// every tick must not write on every tick.
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Text file of `name value` lines holding the last written actuator values.
+pub struct StateFile {
+    path: PathBuf,
+    saved: Option<Vec<(String, i16)>>,
+    last_write: Option<Instant>,
+    writes: usize,
+}
+
+impl StateFile {
+    pub fn new(path: impl Into<PathBuf>) -> StateFile {
+        StateFile {
+            path: path.into(),
+            saved: None,
+            last_write: None,
+            writes: 0,
+        }
+    }
+    /// The state file named by `STATE_FILE_ENV`, if set.
+    pub fn from_env() -> Option<StateFile> {
+        std::env::var_os(STATE_FILE_ENV).map(StateFile::new)
+    }
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Returns the stored entries, or none if the file does not exist yet.
+    /// Malformed lines are skipped.
+    pub fn load(&mut self) -> io::Result<Vec<(String, i16)>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let entries: Vec<(String, i16)> = contents
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let name = fields.next()?;
+                let value = fields.next()?.parse().ok()?;
+                Some((name.to_string(), value))
+            })
+            .collect();
+        self.saved = Some(entries.clone());
+        Ok(entries)
+    }
+    /// Number of times the file has been written.
+    pub fn writes(&self) -> usize {
+        self.writes
+    }
+    /// Writes the entries unless they equal what was last written.
+    pub fn save(&mut self, entries: &[(String, i16)]) -> io::Result<()> {
+        if self.saved.as_deref() == Some(entries) {
+            return Ok(());
+        }
+        let contents: String = entries
+            .iter()
+            .map(|(name, value)| format!("{} {}\n", name, value))
+            .collect();
+        write_atomic(&self.path, &contents)?;
+        self.saved = Some(entries.to_vec());
+        self.writes += 1;
+        Ok(())
+    }
+    /// Like `save`, but skips the write while the last one is less than
+    /// `SAVE_INTERVAL` old. Call it again with the current entries and the
+    /// last change is written once the interval has passed.
+    pub fn save_at(&mut self, entries: &[(String, i16)], now: Instant) -> io::Result<()> {
+        if self.saved.as_deref() == Some(entries) {
+            return Ok(());
+        }
+        if self
+            .last_write
+            .is_some_and(|last| now.saturating_duration_since(last) < SAVE_INTERVAL)
+        {
+            return Ok(());
+        }
+        self.last_write = Some(now);
+        self.save(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_entries() {
+        let path = std::env::temp_dir().join(format!("p9n_state_test_{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut state = StateFile::new(&path);
+        assert!(state.load().unwrap().is_empty());
+        let entries = vec![("md0".to_string(), 25), ("md3".to_string(), -12)];
+        state.save(&entries).unwrap();
+        assert_eq!(StateFile::new(&path).load().unwrap(), entries);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn throttles_timed_saves() {
+        let path = std::env::temp_dir().join(format!("p9n_state_throttle_{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let start = Instant::now();
+        let mut state = StateFile::new(&path);
+        let entries = |value| vec![("md0".to_string(), value)];
+        state.save_at(&entries(1), start).unwrap();
+        state.save_at(&entries(2), start + Duration::from_millis(500)).unwrap();
+        assert_eq!(state.writes(), 1);
+        state.save_at(&entries(2), start + SAVE_INTERVAL).unwrap();
+        assert_eq!(state.writes(), 2);
+        assert_eq!(StateFile::new(&path).load().unwrap(), entries(2));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn parses_policy() {
+        assert_eq!("Resume".parse(), Ok(StartupPolicy::Resume));
+        assert_eq!(" home ".parse(), Ok(StartupPolicy::Home));
+        assert!("later".parse::<StartupPolicy>().is_err());
+    }
+}
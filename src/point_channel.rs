@@ -0,0 +1,352 @@
+use std::fmt;
+use std::io;
+use std::ops::RangeInclusive;
+use std::time::Instant;
+
+use crate::commands::PointCommand;
+use crate::log::{pr_info, pr_warn, Logger};
+use crate::persist::StateFile;
+use crate::sink::Sink;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointChannel {
+    Md0,
+    Md1,
+    Md2,
+    Md3,
+    Md4,
+    Md5,
+}
+
+impl PointChannel {
+    pub const ALL: [PointChannel; 6] = [
+        PointChannel::Md0,
+        PointChannel::Md1,
+        PointChannel::Md2,
+        PointChannel::Md3,
+        PointChannel::Md4,
+        PointChannel::Md5,
+    ];
+    pub fn index(self) -> usize {
+        self as usize
+    }
+    /// Range of the underlying `PointDrive` field (md0/md1 are `i16`, the rest `i8`).
+    pub fn native_range(self) -> RangeInclusive<i16> {
+        match self {
+            PointChannel::Md0 | PointChannel::Md1 => i16::MIN..=i16::MAX,
+            _ => i8::MIN as i16..=i8::MAX as i16,
+        }
+    }
+}
+
+impl fmt::Display for PointChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "md{}", self.index())
+    }
+}
+
+/// What a robot uses a `PointDrive` channel for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelKind {
+    Servo(&'static str),
+    Motor(&'static str),
+    Toggle(&'static str),
+    Code(&'static str),
+    Mode,
+}
+
+impl ChannelKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChannelKind::Servo(name)
+            | ChannelKind::Motor(name)
+            | ChannelKind::Toggle(name)
+            | ChannelKind::Code(name) => name,
+            ChannelKind::Mode => "mode",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelError {
+    Undeclared(PointChannel),
+    OutOfRange {
+        channel: PointChannel,
+        name: &'static str,
+        value: i16,
+        range: RangeInclusive<i16>,
+    },
+}
+
+impl fmt::Display for ChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChannelError::Undeclared(channel) => write!(f, "{} is not declared", channel),
+            ChannelError::OutOfRange { channel, name, value, range } => write!(
+                f,
+                "{} ({}) = {} is outside {}..={}",
+                name,
+                channel,
+                value,
+                range.start(),
+                range.end()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ChannelError {}
+
+struct ChannelSpec {
+    kind: ChannelKind,
+    range: RangeInclusive<i16>,
+}
+
+/// Per-robot declaration of what each `PointDrive` channel means. Owns the
+/// command so every write goes through the declared range check.
+pub struct ChannelMap {
+    specs: [Option<ChannelSpec>; 6],
+    values: [i16; 6],
+    command: PointCommand,
+    state: Option<StateFile>,
+    state_error: Option<io::Error>,
+}
+
+impl ChannelMap {
+    pub fn new() -> ChannelMap {
+        ChannelMap {
+            specs: Default::default(),
+            values: [0; 6],
+            command: PointCommand::default(),
+            state: None,
+            state_error: None,
+        }
+    }
+    /// Declares a channel. The range is narrowed to what the field can hold.
+    /// Panics on an empty range, which would reject every write.
+    pub fn declare(mut self, channel: PointChannel, kind: ChannelKind, range: RangeInclusive<i16>) -> ChannelMap {
+        assert!(
+            range.start() <= range.end(),
+            "{} ({}) declared with empty range {}..={}",
+            kind.name(),
+            channel,
+            range.start(),
+            range.end()
+        );
+        let native = channel.native_range();
+        let start = (*range.start()).clamp(*native.start(), *native.end());
+        let end = (*range.end()).clamp(*native.start(), *native.end());
+        self.specs[channel.index()] = Some(ChannelSpec { kind, range: start..=end });
+        self
+    }
+    /// Saves the servo channels to `state` from `save_state_at`. Other kinds
+    /// are never persisted, so motors always start stopped.
+    pub fn persist_to(mut self, state: StateFile) -> ChannelMap {
+        self.state = Some(state);
+        self
+    }
+    /// Writes the servo values stored in the state file back into the map
+    /// without saving them again. Entries that are undeclared, not servos or
+    /// out of range are skipped. Returns the restored channels.
+    pub fn restore(&mut self) -> io::Result<Vec<PointChannel>> {
+        let Some(state) = self.state.as_mut() else {
+            return Ok(Vec::new());
+        };
+        let entries = state.load()?;
+        let mut restored = Vec::new();
+        for (name, value) in entries {
+            let Some(channel) = PointChannel::ALL.into_iter().find(|c| c.to_string() == name) else {
+                continue;
+            };
+            let is_servo = matches!(self.kind(channel), Some(ChannelKind::Servo(_)));
+            if is_servo && self.check(channel, value).is_ok() {
+                self.set(channel, value);
+                restored.push(channel);
+            }
+        }
+        Ok(restored)
+    }
+    pub fn state_file(&self) -> Option<&StateFile> {
+        self.state.as_ref()
+    }
+    /// The error from the last failed state file save, if any.
+    pub fn take_state_error(&mut self) -> Option<io::Error> {
+        self.state_error.take()
+    }
+    pub fn kind(&self, channel: PointChannel) -> Option<ChannelKind> {
+        self.specs[channel.index()].as_ref().map(|spec| spec.kind)
+    }
+    pub fn name(&self, channel: PointChannel) -> &'static str {
+        self.kind(channel).map(|kind| kind.name()).unwrap_or("undeclared")
+    }
+    pub fn range(&self, channel: PointChannel) -> Option<RangeInclusive<i16>> {
+        self.specs[channel.index()].as_ref().map(|spec| spec.range.clone())
+    }
+    pub fn get(&self, channel: PointChannel) -> i16 {
+        self.values[channel.index()]
+    }
+    /// Looks a channel up by the name it was declared with.
+    pub fn find(&self, name: &str) -> Option<PointChannel> {
+        PointChannel::ALL
+            .into_iter()
+            .find(|channel| self.kind(*channel).map(|kind| kind.name()) == Some(name))
+    }
+    pub fn check(&self, channel: PointChannel, value: i16) -> Result<(), ChannelError> {
+        let spec = self.specs[channel.index()]
+            .as_ref()
+            .ok_or(ChannelError::Undeclared(channel))?;
+        if !spec.range.contains(&value) {
+            return Err(ChannelError::OutOfRange {
+                channel,
+                name: spec.kind.name(),
+                value,
+                range: spec.range.clone(),
+            });
+        }
+        Ok(())
+    }
+    pub fn write(&mut self, channel: PointChannel, value: i16) -> Result<(), ChannelError> {
+        self.check(channel, value)?;
+        self.set(channel, value);
+        Ok(())
+    }
+    fn set(&mut self, channel: PointChannel, value: i16) {
+        self.values[channel.index()] = value;
+        match channel {
+            PointChannel::Md0 => self.command.md0 = value,
+            PointChannel::Md1 => self.command.md1 = value,
+            PointChannel::Md2 => self.command.md2 = value as i8,
+            PointChannel::Md3 => self.command.md3 = value as i8,
+            PointChannel::Md4 => self.command.md4 = value as i8,
+            PointChannel::Md5 => self.command.md5 = value as i8,
+        }
+    }
+    /// Saves the servo values if they changed, at most once per
+    /// `SAVE_INTERVAL`. Call it on every tick so the last change of a move
+    /// is written shortly after the move ends.
+    pub fn save_state_at(&mut self, now: Instant) {
+        if self.state.is_none() {
+            return;
+        }
+        let entries: Vec<(String, i16)> = PointChannel::ALL
+            .into_iter()
+            .filter(|channel| matches!(self.kind(*channel), Some(ChannelKind::Servo(_))))
+            .map(|channel| (channel.to_string(), self.get(channel)))
+            .collect();
+        if let Some(state) = self.state.as_mut() {
+            if let Err(e) = state.save_at(&entries, now) {
+                self.state_error = Some(e);
+            }
+        }
+    }
+    /// Checks every write first and applies none of them if one is rejected.
+    pub fn write_all(&mut self, writes: &[(PointChannel, i16)]) -> Result<(), ChannelError> {
+        for (channel, value) in writes {
+            self.check(*channel, *value)?;
+        }
+        for (channel, value) in writes {
+            self.set(*channel, *value);
+        }
+        Ok(())
+    }
+    /// Applies `writes` and sends the command, logging the changed channels
+    /// by name. Rejected writes are logged and nothing is sent.
+    pub fn publish(
+        &mut self,
+        writes: &[(PointChannel, i16)],
+        publisher: &dyn Sink<PointCommand>,
+        logger: &Logger,
+    ) -> Result<(), ChannelError> {
+        if let Err(e) = self.write_all(writes) {
+            pr_warn!(logger, "{}", e);
+            return Err(e);
+        }
+        let changed = writes
+            .iter()
+            .map(|(channel, value)| format!("{}={}", self.name(*channel), value))
+            .collect::<Vec<_>>()
+            .join(" ");
+        pr_info!(logger, "{}", changed);
+        publisher.send(&self.command);
+        Ok(())
+    }
+    pub fn command(&self) -> &PointCommand {
+        &self.command
+    }
+    /// Declared channels and their current values, e.g. `mode=1 wrist=50`.
+    pub fn describe(&self) -> String {
+        PointChannel::ALL
+            .into_iter()
+            .filter(|channel| self.kind(*channel).is_some())
+            .map(|channel| format!("{}={}", self.name(channel), self.get(channel)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl Default for ChannelMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Sent(RefCell<Vec<PointCommand>>);
+
+    impl Sink<PointCommand> for Sent {
+        fn send(&self, command: &PointCommand) {
+            self.0.borrow_mut().push(*command);
+        }
+    }
+
+    #[test]
+    fn rejected_writes_name_the_channel() {
+        let mut channels = ChannelMap::new()
+            .declare(PointChannel::Md2, ChannelKind::Servo("wrist"), 25..=125)
+            .declare(PointChannel::Md3, ChannelKind::Servo("claw"), 25..=125);
+        let sent = Sent::default();
+        let logger = Logger::new("p9n_interface_2024");
+        let e = channels
+            .publish(&[(PointChannel::Md2, 80), (PointChannel::Md3, 200)], &sent, &logger)
+            .unwrap_err();
+        assert_eq!(e.to_string(), "claw (md3) = 200 is outside 25..=125");
+        assert!(sent.0.borrow().is_empty());
+        assert_eq!(channels.get(PointChannel::Md2), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "wrist (md2) declared with empty range 125..=25")]
+    fn inverted_range_is_refused() {
+        let _ = ChannelMap::new().declare(PointChannel::Md2, ChannelKind::Servo("wrist"), RangeInclusive::new(125, 25));
+    }
+
+    proptest! {
+        #[test]
+        fn fields_never_wrap(
+            ranges in prop::collection::vec((any::<i16>(), any::<i16>()), 6),
+            writes in prop::collection::vec((0..6usize, any::<i16>()), 0..40),
+        ) {
+            let mut channels = ChannelMap::new();
+            for (channel, (a, b)) in PointChannel::ALL.into_iter().zip(ranges) {
+                channels = channels.declare(channel, ChannelKind::Motor("m"), a.min(b)..=a.max(b));
+            }
+            for (index, value) in writes {
+                let channel = PointChannel::ALL[index];
+                let accepted = channels.write(channel, value).is_ok();
+                prop_assert_eq!(accepted, channels.range(channel).unwrap().contains(&value));
+                prop_assert!(channel.native_range().contains(&channels.get(channel)));
+                for channel in PointChannel::ALL {
+                    prop_assert_eq!(channels.command().values()[channel.index()], channels.get(channel));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::persist::write_atomic;
+
+/// Numbered slots of stored joint positions, optionally backed by a text
+/// file with one `slot value value ...` line per filled slot.
+pub struct PoseMemory {
+    slots: Vec<Option<Vec<f32>>>,
+    path: Option<PathBuf>,
+}
+
+impl PoseMemory {
+    pub fn new(slot_count: usize) -> PoseMemory {
+        PoseMemory {
+            slots: vec![None; slot_count],
+            path: None,
+        }
+    }
+    /// Loads the slots from `path`. A missing file gives empty slots; the file
+    /// is created on the first `store`.
+    pub fn load(path: impl Into<PathBuf>, slot_count: usize) -> io::Result<PoseMemory> {
+        let path = path.into();
+        let mut memory = PoseMemory::new(slot_count);
+        match fs::read_to_string(&path) {
+            Ok(contents) => memory.parse(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        memory.path = Some(path);
+        Ok(memory)
+    }
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+    pub fn recall(&self, slot: usize) -> Option<&[f32]> {
+        self.slots.get(slot)?.as_deref()
+    }
+    pub fn store(&mut self, slot: usize, pose: &[f32]) -> io::Result<()> {
+        let Some(entry) = self.slots.get_mut(slot) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("no pose slot {}", slot)));
+        };
+        *entry = Some(pose.to_vec());
+        self.save()
+    }
+    pub fn save(&self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => write_atomic(path, &self.serialize()),
+            None => Ok(()),
+        }
+    }
+    fn serialize(&self) -> String {
+        let mut contents = String::new();
+        for (slot, pose) in self.slots.iter().enumerate() {
+            if let Some(pose) = pose {
+                contents.push_str(&slot.to_string());
+                for value in pose {
+                    contents.push(' ');
+                    contents.push_str(&value.to_string());
+                }
+                contents.push('\n');
+            }
+        }
+        contents
+    }
+    fn parse(&mut self, contents: &str) {
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+            let Some(Ok(slot)) = fields.next().map(str::parse::<usize>) else {
+                continue;
+            };
+            let Ok(pose) = fields.map(str::parse::<f32>).collect::<Result<Vec<_>, _>>() else {
+                continue;
+            };
+            if pose.iter().all(|value| value.is_finite()) {
+                if let Some(entry) = self.slots.get_mut(slot) {
+                    *entry = Some(pose);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_survive_reload() {
+        let path = std::env::temp_dir().join(format!("p9n_pose_test_{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut memory = PoseMemory::load(&path, 2).unwrap();
+        assert_eq!(memory.recall(0), None);
+        memory.store(1, &[25.0, 80.5, 125.0, 40.0]).unwrap();
+        assert!(memory.store(2, &[0.0]).is_err());
+        let memory = PoseMemory::load(&path, 2).unwrap();
+        assert_eq!(memory.recall(0), None);
+        assert_eq!(memory.recall(1), Some(&[25.0, 80.5, 125.0, 40.0][..]));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let mut memory = PoseMemory::new(3);
+        memory.parse("0 25 80\nx 1 2\n1 25 NaN\n2 30 abc\n7 1 2\n");
+        assert_eq!(memory.recall(0), Some(&[25.0, 80.0][..]));
+        assert_eq!(memory.recall(1), None);
+        assert_eq!(memory.recall(2), None);
+    }
+}
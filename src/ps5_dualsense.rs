@@ -24,4 +24,15 @@ pub mod BUTTONS_DUALSENSE {
     pub const PS: usize = 10;
     pub const STICK_L_PUSH: usize = 11;
     pub const STICK_R_PUSH: usize = 12;
-}
\ No newline at end of file
+}
+/// Layout of the `sensor_msgs/Joy` touch topic: coordinates in axes,
+/// contact flags in buttons.
+#[allow(non_snake_case,non_upper_case_globals)]
+pub mod TOUCH_DUALSENSE {
+    pub const X0: usize = 0;
+    pub const Y0: usize = 1;
+    pub const X1: usize = 2;
+    pub const Y1: usize = 3;
+    pub const ACTIVE0: usize = 0;
+    pub const ACTIVE1: usize = 1;
+}
@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Time for the ejector mechanism on the robot2 arms to reset before it can
+/// be triggered again.
+pub const EJECTOR_COOLDOWN: Duration = Duration::from_millis(400);
+
+/// Why a press was ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suppressed {
+    Cooldown { remaining: Duration },
+    Window { max: usize, window: Duration },
+}
+
+impl fmt::Display for Suppressed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Suppressed::Cooldown { remaining } => write!(f, "cooling down for {} ms", remaining.as_millis()),
+            Suppressed::Window { max, window } => {
+                write!(f, "already triggered {} times in {} ms", max, window.as_millis())
+            }
+        }
+    }
+}
+
+/// Cooldown and activations-per-window limit for one binding. Only accepted
+/// presses count towards either limit.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    cooldown: Duration,
+    window: Option<(usize, Duration)>,
+    history: VecDeque<Instant>,
+}
+
+impl RateLimit {
+    pub fn new(cooldown: Duration) -> RateLimit {
+        RateLimit {
+            cooldown,
+            window: None,
+            history: VecDeque::new(),
+        }
+    }
+    /// Allows at most `max` activations in any `window`.
+    pub fn with_window(mut self, max: usize, window: Duration) -> RateLimit {
+        self.window = Some((max, window));
+        self
+    }
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+    /// Records an activation at `now` unless a limit forbids it.
+    pub fn try_fire_at(&mut self, now: Instant) -> Result<(), Suppressed> {
+        if let Some(&last) = self.history.back() {
+            let elapsed = now.saturating_duration_since(last);
+            if elapsed < self.cooldown {
+                return Err(Suppressed::Cooldown { remaining: self.cooldown - elapsed });
+            }
+        }
+        let keep = self.window.map_or(Duration::ZERO, |(_, window)| window);
+        while let Some(&first) = self.history.front() {
+            if now.saturating_duration_since(first) >= keep {
+                self.history.pop_front();
+            } else {
+                break;
+            }
+        }
+        if let Some((max, window)) = self.window {
+            if self.history.len() >= max {
+                return Err(Suppressed::Window { max, window });
+            }
+        }
+        self.history.push_back(now);
+        Ok(())
+    }
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn cooldown_blocks_retrigger() {
+        let start = Instant::now();
+        let mut limit = RateLimit::new(300 * MS);
+        assert!(limit.try_fire_at(start).is_ok());
+        assert_eq!(
+            limit.try_fire_at(start + 100 * MS),
+            Err(Suppressed::Cooldown { remaining: 200 * MS })
+        );
+        // A suppressed press does not restart the cooldown.
+        assert!(limit.try_fire_at(start + 300 * MS).is_ok());
+    }
+
+    #[test]
+    fn window_limits_bursts() {
+        let start = Instant::now();
+        let mut limit = RateLimit::new(100 * MS).with_window(3, Duration::from_secs(1));
+        for i in 0..3 {
+            assert!(limit.try_fire_at(start + i * 100 * MS).is_ok());
+        }
+        assert!(matches!(limit.try_fire_at(start + 500 * MS), Err(Suppressed::Window { max: 3, .. })));
+        assert!(limit.try_fire_at(start + 1000 * MS).is_ok());
+        limit.reset();
+        assert!(limit.try_fire_at(start + 1000 * MS).is_ok());
+    }
+}
@@ -0,0 +1,432 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::air_budget::AirBudget;
+use crate::behaviour::{Behaviour, DualsenseState};
+use crate::commands::{MdCommand, SdCommand};
+use crate::controller::ControllerState;
+use crate::feedback::{ControllerFeedback, Event};
+use crate::interlock::{Blocked, Condition, Interlocks, Output};
+use crate::log::{pr_info, pr_warn, Logger};
+use crate::macro_sequence::{MacroRunner, MacroSequence, MACRO_PERIOD};
+use crate::mode::{Mode, ModeLights};
+use crate::motor::SlewLimitedMotor;
+use crate::outputs::OutputLayer;
+use crate::p9n_interface::PlaystationInterface;
+use crate::rate_limit::RateLimit;
+use crate::sink::Sink;
+
+pub const SHOOTER_ADDRESS: u8 = 0x00;
+pub const EXHAUST_PORT: u8 = 0;
+pub const FIRE_PORT: u8 = 1;
+pub const LOCK_ADDRESS: u8 = 0x05;
+pub const LOCK_PORT: u8 = 0;
+pub const MOTOR_ADDRESS: u8 = 0x05;
+/// Time for the fire cylinder to reset and the line to recover pressure.
+const FIRE_COOLDOWN: Duration = Duration::from_millis(500);
+/// Shots a full tank is good for; overridden by `P9N_AIR_BUDGET`.
+const AIR_TANK_SHOTS: f32 = 40.0;
+const AIR_WARN_SHOTS: [f32; 3] = [10.0, 5.0, 0.0];
+const DRIVE_POWER: i32 = 1000;
+/// Drive power in `Mode::Precision`, for lining up a shot.
+const PRECISION_POWER: i32 = 300;
+
+/// Shooting cycle as `(port, on)` commands for the shooter solenoids.
+fn shoot_sequence() -> MacroSequence<(u8, bool)> {
+    MacroSequence::new("shoot")
+        .then((EXHAUST_PORT, true))
+        .wait(Duration::from_millis(100))
+        .then((FIRE_PORT, true))
+        .wait(Duration::from_millis(300))
+        .then((FIRE_PORT, false))
+        .wait(Duration::from_millis(100))
+        .then((EXHAUST_PORT, false))
+        .on_cancel((FIRE_PORT, false))
+        .on_cancel((EXHAUST_PORT, false))
+}
+
+fn interlocks() -> Interlocks {
+    let exhaust = Output::Solenoid { address: SHOOTER_ADDRESS, port: EXHAUST_PORT };
+    let fire = Output::Solenoid { address: SHOOTER_ADDRESS, port: FIRE_PORT };
+    let lock = Output::Solenoid { address: LOCK_ADDRESS, port: LOCK_PORT };
+    let motor = Output::Motor { address: MOTOR_ADDRESS };
+    Interlocks::new()
+        .rule("fire only with the exhaust closed", fire, Condition::On(exhaust))
+        .rule("no drive while locked", motor, Condition::Off(lock))
+}
+
+fn air_budget() -> AirBudget {
+    AirBudget::from_env(AIR_TANK_SHOTS)
+        .with_cost(SHOOTER_ADDRESS, EXHAUST_PORT, 0.5)
+        .with_cost(LOCK_ADDRESS, LOCK_PORT, 0.2)
+        .with_thresholds(&AIR_WARN_SHOTS)
+}
+
+/// Logs `blocked` and rumbles the controller so the operator knows the
+/// button did nothing.
+fn report_blocked(blocked: &Blocked, logger: &Logger, feedback: &RefCell<ControllerFeedback>, now: Instant) {
+    pr_warn!(logger, "{}", blocked);
+    feedback.borrow_mut().signal_at(Event::Blocked, now);
+}
+
+fn send_solenoid(
+    result: Result<Option<SdCommand>, Blocked>,
+    sd_publisher: &dyn Sink<SdCommand>,
+    logger: &Logger,
+    feedback: &RefCell<ControllerFeedback>,
+    now: Instant,
+) -> Result<(), Blocked> {
+    match result {
+        Ok(Some(sd_msg)) => {
+            sd_publisher.send(&sd_msg);
+            Ok(())
+        }
+        Ok(None) => Ok(()),
+        Err(blocked) => {
+            report_blocked(&blocked, logger, feedback, now);
+            Err(blocked)
+        }
+    }
+}
+
+fn send_shooter(
+    outputs: &mut OutputLayer,
+    commands: &[(u8, bool)],
+    sd_publisher: &dyn Sink<SdCommand>,
+    logger: &Logger,
+    feedback: &RefCell<ControllerFeedback>,
+    now: Instant,
+) -> Result<(), Blocked> {
+    for (port, on) in commands {
+        send_solenoid(outputs.set_solenoid(SHOOTER_ADDRESS, *port, *on), sd_publisher, logger, feedback, now)?;
+    }
+    Ok(())
+}
+
+/// Shooter, lock and drive motor on the MD/SD boards.
+pub struct Robot1 {
+    p9n: PlaystationInterface,
+    logger: Logger,
+    dualsense_state: [bool; 15],
+    fire_limit: RateLimit,
+    outputs: OutputLayer,
+    shoot: MacroSequence<(u8, bool)>,
+    macro_runner: MacroRunner<(u8, bool)>,
+    mode_lights: ModeLights,
+    feedback: Rc<RefCell<ControllerFeedback>>,
+    air_status: String,
+    md_publisher: Box<dyn Sink<MdCommand>>,
+    sd_publisher: Box<dyn Sink<SdCommand>>,
+    air_publisher: Box<dyn Sink<String>>,
+}
+
+impl Robot1 {
+    /// Starts disarmed and shows the disarmed lights on `feedback` as of
+    /// `now`.
+    pub fn new_at(
+        md_publisher: Box<dyn Sink<MdCommand>>,
+        sd_publisher: Box<dyn Sink<SdCommand>>,
+        air_publisher: Box<dyn Sink<String>>,
+        feedback: Rc<RefCell<ControllerFeedback>>,
+        now: Instant,
+    ) -> Robot1 {
+        let mode_lights = ModeLights::new(Mode::Disarmed);
+        mode_lights.show_at(&mut feedback.borrow_mut(), now);
+        Robot1 {
+            p9n: PlaystationInterface::from_state(ControllerState::default()),
+            logger: Logger::new("p9n_interface_2024"),
+            dualsense_state: [false; 15],
+            fire_limit: RateLimit::new(FIRE_COOLDOWN).with_window(5, Duration::from_secs(5)),
+            outputs: OutputLayer::new()
+                .with_solenoids(SHOOTER_ADDRESS)
+                .with_solenoids(LOCK_ADDRESS)
+                .with_motor(SlewLimitedMotor::new(MOTOR_ADDRESS, 2, 2000.0, 4000.0))
+                .with_interlocks(interlocks())
+                .with_air_budget(air_budget()),
+            shoot: shoot_sequence(),
+            macro_runner: MacroRunner::new(),
+            mode_lights,
+            feedback,
+            air_status: String::new(),
+            md_publisher,
+            sd_publisher,
+            air_publisher,
+        }
+    }
+    pub fn mode(&self) -> Mode {
+        self.mode_lights.mode()
+    }
+}
+
+impl Behaviour for Robot1 {
+    fn tick_period(&self) -> Option<Duration> {
+        Some(MACRO_PERIOD)
+    }
+
+    fn tick_at(&mut self, now: Instant) {
+        let commands = self.macro_runner.tick_at(now);
+        if send_shooter(&mut self.outputs, &commands, &*self.sd_publisher, &self.logger, &self.feedback, now).is_err() {
+            pr_warn!(self.logger, "macro cancelled");
+            let commands = self.macro_runner.cancel();
+            let _ = send_shooter(&mut self.outputs, &commands, &*self.sd_publisher, &self.logger, &self.feedback, now);
+        }
+        let (sd_msgs, md_msgs) = self.outputs.tick_at(now);
+        for sd_msg in sd_msgs {
+            self.sd_publisher.send(&sd_msg);
+        }
+        for md_msg in md_msgs {
+            self.md_publisher.send(&md_msg);
+        }
+
+        for warning in self.outputs.take_air_warnings() {
+            pr_warn!(self.logger, "{}", warning);
+            self.feedback.borrow_mut().signal_at(Event::AirLow, now);
+        }
+        if let Some(air_budget) = self.outputs.air_budget() {
+            let status = air_budget.status();
+            if status != self.air_status {
+                self.air_publisher.send(&status);
+                self.air_status = status;
+            }
+        }
+    }
+
+    fn on_state_at(&mut self, state: ControllerState, now: Instant) {
+        self.p9n.set_state(state);
+        let p9n = &self.p9n;
+        let logger = &self.logger;
+        let dualsense_state = &mut self.dualsense_state;
+        let outputs = &mut self.outputs;
+        let macro_runner = &mut self.macro_runner;
+        let sd_publisher = &*self.sd_publisher;
+        let feedback = &*self.feedback;
+
+        if p9n.pressed_ps() && !dualsense_state[DualsenseState::PS] {
+            pr_info!(logger, "emergency stop");
+            dualsense_state[DualsenseState::PS] = true;
+            self.mode_lights.set_at(Mode::EStop, &mut feedback.borrow_mut(), now);
+            for md_msg in outputs.emergency_stop() {
+                self.md_publisher.send(&md_msg);
+            }
+            if macro_runner.is_running() {
+                let _ = send_shooter(outputs, &macro_runner.cancel(), sd_publisher, logger, feedback, now);
+            }
+        }
+        if !p9n.pressed_ps() && dualsense_state[DualsenseState::PS] {
+            dualsense_state[DualsenseState::PS] = false;
+        }
+
+        // Select arms, and disarms when armed; after an e-stop it is the
+        // only way back to driving.
+        if p9n.pressed_select() && !dualsense_state[DualsenseState::SELECT] {
+            dualsense_state[DualsenseState::SELECT] = true;
+            if self.mode_lights.mode().is_armed() {
+                let _ = outputs.set_motor_at(MOTOR_ADDRESS, 0, now);
+                if macro_runner.is_running() {
+                    let _ = send_shooter(outputs, &macro_runner.cancel(), sd_publisher, logger, feedback, now);
+                }
+                self.mode_lights.set_at(Mode::Disarmed, &mut feedback.borrow_mut(), now);
+            } else {
+                self.mode_lights.set_at(Mode::Armed, &mut feedback.borrow_mut(), now);
+            }
+            pr_info!(logger, "{:?}", self.mode_lights.mode());
+        }
+        if !p9n.pressed_select() && dualsense_state[DualsenseState::SELECT] {
+            dualsense_state[DualsenseState::SELECT] = false;
+        }
+        if p9n.pressed_triangle() && !dualsense_state[DualsenseState::TRIANGLE] {
+            dualsense_state[DualsenseState::TRIANGLE] = true;
+            let mode = match self.mode_lights.mode() {
+                Mode::Armed => Some(Mode::Precision),
+                Mode::Precision => Some(Mode::Armed),
+                _ => None,
+            };
+            if let Some(mode) = mode {
+                self.mode_lights.set_at(mode, &mut feedback.borrow_mut(), now);
+                pr_info!(logger, "{:?}", mode);
+            }
+        }
+        if !p9n.pressed_triangle() && dualsense_state[DualsenseState::TRIANGLE] {
+            dualsense_state[DualsenseState::TRIANGLE] = false;
+        }
+        let armed = self.mode_lights.mode().is_armed();
+        let drive_power = if self.mode_lights.mode() == Mode::Precision { PRECISION_POWER } else { DRIVE_POWER };
+
+        if p9n.pressed_start() && !dualsense_state[DualsenseState::START] {
+            dualsense_state[DualsenseState::START] = true;
+            if let Some(air_budget) = outputs.air_budget_mut() {
+                air_budget.refill();
+                pr_info!(logger, "air tank refilled: {}", air_budget.status());
+            }
+        }
+        if !p9n.pressed_start() && dualsense_state[DualsenseState::START] {
+            dualsense_state[DualsenseState::START] = false;
+        }
+
+        if p9n.pressed_circle() && !dualsense_state[DualsenseState::CIRCLE] && armed {
+            dualsense_state[DualsenseState::CIRCLE] = true;
+            match macro_runner.start_at(&self.shoot, now) {
+                Some(commands) => {
+                    pr_info!(logger, "macro {} started", self.shoot.name());
+                    if send_shooter(outputs, &commands, sd_publisher, logger, feedback, now).is_err() {
+                        let _ = send_shooter(outputs, &macro_runner.cancel(), sd_publisher, logger, feedback, now);
+                    }
+                }
+                None => pr_info!(logger, "macro {:?} still running", macro_runner.current()),
+            }
+        }
+        if !p9n.pressed_circle() && dualsense_state[DualsenseState::CIRCLE] {
+            dualsense_state[DualsenseState::CIRCLE] = false;
+        }
+        if p9n.pressed_square() && !dualsense_state[DualsenseState::SQUARE] {
+            dualsense_state[DualsenseState::SQUARE] = true;
+            if macro_runner.is_running() {
+                pr_info!(logger, "macro {:?} cancelled", macro_runner.current());
+                let _ = send_shooter(outputs, &macro_runner.cancel(), sd_publisher, logger, feedback, now);
+            }
+        }
+        if !p9n.pressed_square() && dualsense_state[DualsenseState::SQUARE] {
+            dualsense_state[DualsenseState::SQUARE] = false;
+        }
+
+        if p9n.pressed_dpad_left() && !dualsense_state[DualsenseState::D_PAD_LEFT] && armed && !macro_runner.is_running() {
+            pr_info!(logger, "left");
+            dualsense_state[DualsenseState::D_PAD_LEFT] = true;
+            let _ = send_solenoid(outputs.toggle_solenoid(SHOOTER_ADDRESS, EXHAUST_PORT), sd_publisher, logger, feedback, now);
+        }
+        if !p9n.pressed_dpad_left() && dualsense_state[DualsenseState::D_PAD_LEFT] {
+            pr_info!(logger, "reverse left");
+            dualsense_state[DualsenseState::D_PAD_LEFT] = false;
+        }
+        if p9n.pressed_dpad_right() && !dualsense_state[DualsenseState::D_PAD_RIGHT] && armed && !macro_runner.is_running() {
+            pr_info!(logger, "right");
+            dualsense_state[DualsenseState::D_PAD_RIGHT] = true;
+            if let Err(suppressed) = self.fire_limit.try_fire_at(now) {
+                pr_info!(logger, "fire suppressed: {}", suppressed);
+            } else {
+                let _ = send_solenoid(outputs.set_solenoid(SHOOTER_ADDRESS, FIRE_PORT, true), sd_publisher, logger, feedback, now);
+            }
+        }
+        if !p9n.pressed_dpad_right() && dualsense_state[DualsenseState::D_PAD_RIGHT] {
+            pr_info!(logger, "reverse right");
+            dualsense_state[DualsenseState::D_PAD_RIGHT] = false;
+            let _ = send_shooter(outputs, &[(FIRE_PORT, false), (EXHAUST_PORT, false)], sd_publisher, logger, feedback, now);
+        }
+        if p9n.pressed_dpad_up() && !dualsense_state[DualsenseState::D_PAD_UP] && armed {
+            pr_info!(logger, "up");
+            dualsense_state[DualsenseState::D_PAD_UP] = true;
+            if let Err(blocked) = outputs.set_motor_at(MOTOR_ADDRESS, if !p9n.pressed_cross() {drive_power} else {-drive_power}, now) {
+                report_blocked(&blocked, logger, feedback, now);
+            }
+        }
+        if !p9n.pressed_dpad_up() && dualsense_state[DualsenseState::D_PAD_UP] {
+            pr_info!(logger, "reverse up");
+            dualsense_state[DualsenseState::D_PAD_UP] = false;
+            let _ = outputs.set_motor_at(MOTOR_ADDRESS, 0, now);
+        }
+        if p9n.pressed_dpad_down() && !dualsense_state[DualsenseState::D_PAD_DOWN] {
+            pr_info!(logger, "down");
+            dualsense_state[DualsenseState::D_PAD_DOWN] = true;
+            let _ = send_solenoid(outputs.set_solenoid(LOCK_ADDRESS, LOCK_PORT, true), sd_publisher, logger, feedback, now);
+        }
+        if !p9n.pressed_dpad_down() && dualsense_state[DualsenseState::D_PAD_DOWN] {
+            pr_info!(logger, "reverse down");
+            dualsense_state[DualsenseState::D_PAD_DOWN] = false;
+            let _ = send_solenoid(outputs.set_solenoid(LOCK_ADDRESS, LOCK_PORT, false), sd_publisher, logger, feedback, now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::harness::{assert_golden, Harness};
+    use crate::ps5_dualsense::{AXES_DUALSENSE, BUTTONS_DUALSENSE};
+    use crate::script::{Script, TAP};
+
+    #[test]
+    fn golden_session() {
+        let harness = Harness::new();
+        let feedback = Rc::new(RefCell::new(ControllerFeedback::new_at(harness.clock().now())));
+        let mut robot = Robot1::new_at(
+            harness.sink("/md_driver_topic"),
+            harness.sink("/sd_driver_topic"),
+            harness.sink("/air_status"),
+            feedback,
+            harness.clock().now(),
+        );
+        let script = Script::new()
+            .tap(BUTTONS_DUALSENSE::CIRCLE)
+            .tap(BUTTONS_DUALSENSE::SELECT)
+            .tap(BUTTONS_DUALSENSE::CIRCLE)
+            .wait(Duration::from_millis(600))
+            .push(AXES_DUALSENSE::DPAD_Y, 1.0, Duration::from_millis(300))
+            .wait(Duration::from_millis(500))
+            .push(AXES_DUALSENSE::DPAD_Y, -1.0, TAP)
+            .push(AXES_DUALSENSE::DPAD_Y, 1.0, TAP)
+            .wait(TAP)
+            .tap(BUTTONS_DUALSENSE::TRIANGLE)
+            .push(AXES_DUALSENSE::DPAD_Y, 1.0, Duration::from_millis(300))
+            .wait(Duration::from_millis(300))
+            .tap(BUTTONS_DUALSENSE::PS);
+        harness.run(&mut robot, &script);
+        assert_eq!(robot.mode(), Mode::EStop);
+        assert_golden("robot1", &harness.transcript());
+    }
+
+    #[test]
+    fn e_stop_holds_until_rearmed() {
+        let harness = Harness::new();
+        let feedback = Rc::new(RefCell::new(ControllerFeedback::new_at(harness.clock().now())));
+        let mut robot = Robot1::new_at(
+            harness.sink("/md_driver_topic"),
+            harness.sink("/sd_driver_topic"),
+            harness.sink("/air_status"),
+            feedback,
+            harness.clock().now(),
+        );
+        assert_eq!(robot.mode(), Mode::Disarmed);
+        harness.run(&mut robot, &Script::new().tap(BUTTONS_DUALSENSE::SELECT).tap(BUTTONS_DUALSENSE::PS));
+        assert_eq!(robot.mode(), Mode::EStop);
+        let before = harness.transcript();
+        harness.run(
+            &mut robot,
+            &Script::new()
+                .push(AXES_DUALSENSE::DPAD_Y, 1.0, TAP)
+                .tap(BUTTONS_DUALSENSE::CIRCLE)
+                .tap(BUTTONS_DUALSENSE::TRIANGLE),
+        );
+        assert_eq!(robot.mode(), Mode::EStop);
+        assert_eq!(harness.transcript(), before);
+        harness.run(&mut robot, &Script::new().tap(BUTTONS_DUALSENSE::SELECT));
+        assert_eq!(robot.mode(), Mode::Armed);
+        harness.run(&mut robot, &Script::new().tap(BUTTONS_DUALSENSE::SELECT));
+        assert_eq!(robot.mode(), Mode::Disarmed);
+    }
+
+    #[test]
+    fn blocked_fire_rumbles() {
+        let harness = Harness::new();
+        let feedback = Rc::new(RefCell::new(ControllerFeedback::new_at(harness.clock().now())));
+        let mut robot = Robot1::new_at(
+            harness.sink("/md_driver_topic"),
+            harness.sink("/sd_driver_topic"),
+            harness.sink("/air_status"),
+            feedback.clone(),
+            harness.clock().now(),
+        );
+        let rumble = |feedback: &RefCell<ControllerFeedback>| {
+            feedback.borrow_mut().tick_at(harness.clock().now()).map(|items| items[0].intensity)
+        };
+        assert_eq!(rumble(&feedback), Some(0.0));
+        // Fire with the exhaust still open.
+        harness.run(
+            &mut robot,
+            &Script::new().tap(BUTTONS_DUALSENSE::SELECT).push(AXES_DUALSENSE::DPAD_X, -1.0, TAP),
+        );
+        assert!(!harness.transcript().contains("/sd_driver_topic"));
+        assert_eq!(rumble(&feedback), Some(Event::Blocked.rumble().intensity));
+    }
+}
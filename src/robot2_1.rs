@@ -0,0 +1,132 @@
+use std::time::Instant;
+
+use crate::behaviour::{Behaviour, DualsenseState};
+use crate::commands::PointCommand;
+use crate::controller::ControllerState;
+use crate::log::{pr_info, Logger};
+use crate::p9n_interface::PlaystationInterface;
+use crate::point_channel::{ChannelKind, ChannelMap, PointChannel};
+use crate::rate_limit::{RateLimit, EJECTOR_COOLDOWN};
+use crate::servo::Servo;
+use crate::sink::Sink;
+
+pub const TOPIC: &str = "/point_2_1";
+
+/// Wrist servo, lift motor and ejector on `/point_2_1`.
+pub struct Robot2_1 {
+    p9n: PlaystationInterface,
+    logger: Logger,
+    dualsense_state: [bool; 15],
+    ejector_limit: RateLimit,
+    channels: ChannelMap,
+    wrist_servo: Servo,
+    publisher: Box<dyn Sink<PointCommand>>,
+}
+
+impl Robot2_1 {
+    pub fn new(publisher: Box<dyn Sink<PointCommand>>) -> Robot2_1 {
+        Robot2_1 {
+            p9n: PlaystationInterface::from_state(ControllerState::default()),
+            logger: Logger::new("p9n_interface_2024"),
+            dualsense_state: [false; 15],
+            ejector_limit: RateLimit::new(EJECTOR_COOLDOWN),
+            channels: ChannelMap::new()
+                .declare(PointChannel::Md2, ChannelKind::Mode, 0..=1)
+                .declare(PointChannel::Md3, ChannelKind::Servo("wrist"), 25..=125)
+                .declare(PointChannel::Md4, ChannelKind::Motor("lift"), -1..=1)
+                .declare(PointChannel::Md5, ChannelKind::Toggle("ejector"), 0..=127),
+            wrist_servo: Servo::new(25, 125, 0.0, 180.0),
+            publisher,
+        }
+    }
+}
+
+impl Behaviour for Robot2_1 {
+    fn on_state_at(&mut self, state: ControllerState, now: Instant) {
+        self.p9n.set_state(state);
+        let p9n = &self.p9n;
+        let logger = &self.logger;
+        let dualsense_state = &mut self.dualsense_state;
+        let channels = &mut self.channels;
+        let publisher = &*self.publisher;
+
+        if p9n.pressed_dpad_left() && !dualsense_state[DualsenseState::D_PAD_LEFT] {
+            pr_info!(logger, "left");
+            dualsense_state[DualsenseState::D_PAD_LEFT] = true;
+            let _ = channels.publish(
+                &[(PointChannel::Md2, 1), (PointChannel::Md3, self.wrist_servo.pulse_i8(45.0) as i16)],
+                publisher,
+                logger,
+            );
+        }
+        if !p9n.pressed_dpad_left() && dualsense_state[DualsenseState::D_PAD_LEFT] {
+            pr_info!(logger, "reverse left");
+            dualsense_state[DualsenseState::D_PAD_LEFT] = false;
+            let _ = channels.publish(
+                &[(PointChannel::Md2, 1), (PointChannel::Md3, self.wrist_servo.pulse_i8(135.0) as i16)],
+                publisher,
+                logger,
+            );
+        }
+        if p9n.pressed_dpad_up() {
+            pr_info!(logger, "up");
+            dualsense_state[DualsenseState::D_PAD_UP] = true;
+            let _ = channels.publish(
+                &[(PointChannel::Md2, 0), (PointChannel::Md4, if !p9n.pressed_cross() {1} else {-1})],
+                publisher,
+                logger,
+            );
+        }
+        if !p9n.pressed_dpad_up() && dualsense_state[DualsenseState::D_PAD_UP] {
+            pr_info!(logger, "reverse up");
+            dualsense_state[DualsenseState::D_PAD_UP] = false;
+            let _ = channels.publish(
+                &[(PointChannel::Md2, 0), (PointChannel::Md4, 0)],
+                publisher,
+                logger,
+            );
+        }
+        if p9n.pressed_triangle() && !dualsense_state[DualsenseState::TRIANGLE] {
+            pr_info!(logger, "triangle");
+            dualsense_state[DualsenseState::TRIANGLE] = true;
+            if let Err(suppressed) = self.ejector_limit.try_fire_at(now) {
+                pr_info!(logger, "ejector suppressed: {}", suppressed);
+            } else {
+                let ejector = if channels.get(PointChannel::Md5) <= 0 {127} else {0};
+                let _ = channels.publish(&[(PointChannel::Md5, ejector)], publisher, logger);
+            }
+        }
+        if !p9n.pressed_triangle() && dualsense_state[DualsenseState::TRIANGLE] {
+            pr_info!(logger, "reverse triangle");
+            dualsense_state[DualsenseState::TRIANGLE] = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::harness::{assert_golden, Harness};
+    use crate::ps5_dualsense::{AXES_DUALSENSE, BUTTONS_DUALSENSE};
+    use crate::script::Script;
+
+    #[test]
+    fn golden_session() {
+        let harness = Harness::new();
+        let mut robot = Robot2_1::new(harness.sink(TOPIC));
+        let script = Script::new()
+            .push(AXES_DUALSENSE::DPAD_X, 1.0, Duration::from_millis(200))
+            .push(AXES_DUALSENSE::DPAD_Y, 1.0, Duration::from_millis(60))
+            .hold(BUTTONS_DUALSENSE::CROSS)
+            .push(AXES_DUALSENSE::DPAD_Y, 1.0, Duration::from_millis(60))
+            .release(BUTTONS_DUALSENSE::CROSS)
+            .tap(BUTTONS_DUALSENSE::TRIANGLE)
+            .tap(BUTTONS_DUALSENSE::TRIANGLE)
+            .wait(Duration::from_millis(300))
+            .tap(BUTTONS_DUALSENSE::TRIANGLE);
+        harness.run(&mut robot, &script);
+        assert_golden("robot2_1", &harness.transcript());
+    }
+}
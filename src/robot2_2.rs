@@ -0,0 +1,288 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::behaviour::{Behaviour, DualsenseState};
+use crate::commands::PointCommand;
+use crate::controller::ControllerState;
+use crate::feedback::{ControllerFeedback, Event};
+use crate::joint::{trigger_command, JointIntegrator};
+use crate::log::{pr_info, pr_warn, Logger};
+use crate::motion::{publish_ramps_at, ChannelRamps, MotionProfile, ProfileShape, RAMP_PERIOD};
+use crate::p9n_interface::PlaystationInterface;
+use crate::persist::{StartupPolicy, StateFile};
+use crate::point_channel::{ChannelKind, ChannelMap, PointChannel};
+use crate::pose::PoseMemory;
+use crate::rate_limit::{RateLimit, EJECTOR_COOLDOWN};
+use crate::sink::Sink;
+
+pub const TOPIC: &str = "/point_2_2";
+pub const POSE_FILE: &str = "robot2_2_poses.txt";
+pub const POSE_SLOTS: usize = 2;
+
+const ARM_SPEED: f32 = 50.0;
+const ARM_SOFT_ZONE: f32 = 10.0;
+
+const ARM_PROFILE: MotionProfile = MotionProfile {
+    shape: ProfileShape::Trapezoidal,
+    max_velocity: 100.0,
+    max_acceleration: 400.0,
+};
+const ARM_CHANNELS: [PointChannel; 4] = [PointChannel::Md0, PointChannel::Md1, PointChannel::Md2, PointChannel::Md3];
+
+fn arm_joint() -> JointIntegrator<i16> {
+    JointIntegrator::new(25.0, 25.0, 125.0, ARM_SPEED).with_soft_zone(ARM_SOFT_ZONE, 0.2)
+}
+
+/// Stops a recall move in progress so manual control continues from where
+/// the arm actually is.
+fn take_over(ramps: &mut ChannelRamps, channel: PointChannel, joint: &mut JointIntegrator<i16>) {
+    if let Some(ramp) = ramps.get_mut(channel) {
+        if ramp.is_moving() {
+            let position = ramp.position();
+            ramp.jump_to(position);
+            joint.reset(position);
+        }
+    }
+}
+
+/// Rumbles once when `joint` runs into its limit.
+fn signal_clamp(joint: &JointIntegrator<i16>, was_at_limit: &mut bool, feedback: &RefCell<ControllerFeedback>, now: Instant) {
+    if joint.at_limit() && !*was_at_limit {
+        feedback.borrow_mut().signal_at(Event::Clamp, now);
+    }
+    *was_at_limit = joint.at_limit();
+}
+
+/// Four-joint arm with pose memory and a two-way ejector on `/point_2_2`.
+pub struct Robot2_2 {
+    p9n: PlaystationInterface,
+    logger: Logger,
+    dualsense_state: [bool; 15],
+    ejector_limit: RateLimit,
+    channels: ChannelMap,
+    ramps: ChannelRamps,
+    poses: PoseMemory,
+    feedback: Rc<RefCell<ControllerFeedback>>,
+    arm_at_limit: [bool; 4],
+    arm0_joint: JointIntegrator<i16>,
+    arm1_joint: JointIntegrator<i16>,
+    arm2_joint: JointIntegrator<i16>,
+    arm3_joint: JointIntegrator<i16>,
+    publisher: Box<dyn Sink<PointCommand>>,
+}
+
+impl Robot2_2 {
+    pub fn new(publisher: Box<dyn Sink<PointCommand>>, feedback: Rc<RefCell<ControllerFeedback>>) -> Robot2_2 {
+        Robot2_2 {
+            p9n: PlaystationInterface::from_state(ControllerState::default()),
+            logger: Logger::new("p9n_interface_2024"),
+            dualsense_state: [false; 15],
+            ejector_limit: RateLimit::new(EJECTOR_COOLDOWN),
+            channels: ChannelMap::new()
+                .declare(PointChannel::Md0, ChannelKind::Servo("arm0"), 25..=125)
+                .declare(PointChannel::Md1, ChannelKind::Servo("arm1"), 25..=125)
+                .declare(PointChannel::Md2, ChannelKind::Servo("arm2"), 25..=125)
+                .declare(PointChannel::Md3, ChannelKind::Servo("arm3"), 25..=125)
+                .declare(PointChannel::Md5, ChannelKind::Motor("ejector"), -128..=127),
+            ramps: ARM_CHANNELS
+                .iter()
+                .fold(ChannelRamps::new(), |ramps, channel| ramps.with(*channel, 25.0, ARM_PROFILE)),
+            poses: PoseMemory::new(POSE_SLOTS),
+            feedback,
+            arm_at_limit: [false; 4],
+            arm0_joint: arm_joint(),
+            arm1_joint: arm_joint(),
+            arm2_joint: arm_joint(),
+            arm3_joint: arm_joint(),
+            publisher,
+        }
+    }
+    /// Restores the arm from `state` according to `policy` and keeps saving
+    /// it there. A homing ramp starts at `now`.
+    pub fn with_state_file_at(mut self, state: StateFile, policy: StartupPolicy, now: Instant) -> Robot2_2 {
+        let logger = &self.logger;
+        pr_info!(logger, "actuator state file: {}", state.path().display());
+        self.channels = std::mem::take(&mut self.channels).persist_to(state);
+        match self.channels.restore() {
+            Ok(restored) => {
+                pr_info!(logger, "{:?} from {}", policy, self.channels.describe());
+                self.ramps.start_from_at(&self.channels, &restored, policy, now);
+            }
+            Err(e) => pr_warn!(logger, "could not read actuator state: {}", e),
+        }
+        for (channel, joint) in ARM_CHANNELS.iter().zip([
+            &mut self.arm0_joint,
+            &mut self.arm1_joint,
+            &mut self.arm2_joint,
+            &mut self.arm3_joint,
+        ]) {
+            if let Some(ramp) = self.ramps.get(*channel) {
+                joint.reset(ramp.target());
+            }
+        }
+        self
+    }
+    pub fn with_poses(mut self, poses: PoseMemory) -> Robot2_2 {
+        self.poses = poses;
+        self
+    }
+}
+
+impl Behaviour for Robot2_2 {
+    fn tick_period(&self) -> Option<Duration> {
+        Some(RAMP_PERIOD)
+    }
+
+    fn tick_at(&mut self, now: Instant) {
+        publish_ramps_at(&mut self.ramps, &mut self.channels, &*self.publisher, &self.logger, now);
+    }
+
+    fn on_state_at(&mut self, state: ControllerState, now: Instant) {
+        self.p9n.set_state(state);
+        let p9n = &self.p9n;
+        let logger = &self.logger;
+        let dualsense_state = &mut self.dualsense_state;
+        let channels = &mut self.channels;
+        let ramps = &mut self.ramps;
+        let feedback = &*self.feedback;
+        let arm_at_limit = &mut self.arm_at_limit;
+        let publisher = &*self.publisher;
+
+        if p9n.pressed_l2() {
+            dualsense_state[DualsenseState::L2] = true;
+            take_over(ramps, PointChannel::Md0, &mut self.arm0_joint);
+            let angle = self.arm0_joint.update_at(trigger_command(p9n.pressed_l2_analog()) * if !p9n.pressed_cross() {1.0} else {-1.0}, now);
+            signal_clamp(&self.arm0_joint, &mut arm_at_limit[0], feedback, now);
+            let _ = channels.publish(&[(PointChannel::Md0, angle)], publisher, logger);
+        }
+        if !p9n.pressed_l2() && dualsense_state[DualsenseState::L2] {
+            dualsense_state[DualsenseState::L2] = false;
+            let angle = self.arm0_joint.update_at(0.0, now);
+            let _ = channels.publish(&[(PointChannel::Md0, angle)], publisher, logger);
+        }
+        if p9n.pressed_r2() {
+            dualsense_state[DualsenseState::R2] = true;
+            take_over(ramps, PointChannel::Md1, &mut self.arm1_joint);
+            let angle = self.arm1_joint.update_at(trigger_command(p9n.pressed_r2_analog()) * if !p9n.pressed_cross() {1.0} else {-1.0}, now);
+            signal_clamp(&self.arm1_joint, &mut arm_at_limit[1], feedback, now);
+            let _ = channels.publish(&[(PointChannel::Md1, angle)], publisher, logger);
+        }
+        if !p9n.pressed_r2() && dualsense_state[DualsenseState::R2] {
+            dualsense_state[DualsenseState::R2] = false;
+            let angle = self.arm1_joint.update_at(0.0, now);
+            let _ = channels.publish(&[(PointChannel::Md1, angle)], publisher, logger);
+        }
+        if p9n.pressed_l1() {
+            dualsense_state[DualsenseState::L1] = true;
+            take_over(ramps, PointChannel::Md2, &mut self.arm2_joint);
+            let angle = self.arm2_joint.update_at(if !p9n.pressed_cross() {-1.0} else {1.0}, now);
+            signal_clamp(&self.arm2_joint, &mut arm_at_limit[2], feedback, now);
+            let _ = channels.publish(&[(PointChannel::Md2, angle)], publisher, logger);
+        }
+        if !p9n.pressed_l1() && dualsense_state[DualsenseState::L1] {
+            dualsense_state[DualsenseState::L1] = false;
+            let angle = self.arm2_joint.update_at(0.0, now);
+            let _ = channels.publish(&[(PointChannel::Md2, angle)], publisher, logger);
+        }
+        if p9n.pressed_r1() {
+            dualsense_state[DualsenseState::R1] = true;
+            take_over(ramps, PointChannel::Md3, &mut self.arm3_joint);
+            let angle = self.arm3_joint.update_at(if !p9n.pressed_cross() {1.0} else {-1.0}, now);
+            signal_clamp(&self.arm3_joint, &mut arm_at_limit[3], feedback, now);
+            let _ = channels.publish(&[(PointChannel::Md3, angle)], publisher, logger);
+        }
+        if !p9n.pressed_r1() && dualsense_state[DualsenseState::R1] {
+            pr_info!(logger, "reverse R1");
+            dualsense_state[DualsenseState::R1] = false;
+            let angle = self.arm3_joint.update_at(0.0, now);
+            let _ = channels.publish(&[(PointChannel::Md3, angle)], publisher, logger);
+        }
+        if p9n.pressed_triangle() && !dualsense_state[DualsenseState::TRIANGLE] {
+            pr_info!(logger, "triangle");
+            dualsense_state[DualsenseState::TRIANGLE] = true;
+            if let Err(suppressed) = self.ejector_limit.try_fire_at(now) {
+                pr_info!(logger, "ejector suppressed: {}", suppressed);
+            } else {
+                let ejector = if !p9n.pressed_cross() {127} else {-128};
+                let _ = channels.publish(&[(PointChannel::Md5, ejector)], publisher, logger);
+            }
+        }
+        if !p9n.pressed_triangle() && dualsense_state[DualsenseState::TRIANGLE] {
+            pr_info!(logger, "reverse triangle");
+            dualsense_state[DualsenseState::TRIANGLE] = false;
+        }
+        for (slot, pressed, state) in [
+            (0, p9n.pressed_square(), DualsenseState::SQUARE),
+            (1, p9n.pressed_circle(), DualsenseState::CIRCLE),
+        ] {
+            if pressed && !dualsense_state[state] {
+                dualsense_state[state] = true;
+                if p9n.pressed_select() {
+                    let pose = [
+                        self.arm0_joint.position(),
+                        self.arm1_joint.position(),
+                        self.arm2_joint.position(),
+                        self.arm3_joint.position(),
+                    ];
+                    match self.poses.store(slot, &pose) {
+                        Ok(()) => pr_info!(logger, "stored pose {}: {:?}", slot, pose),
+                        Err(e) => pr_warn!(logger, "could not save pose {}: {}", slot, e),
+                    }
+                } else if let Some(pose) = self.poses.recall(slot) {
+                    pr_info!(logger, "recall pose {}: {:?}", slot, pose);
+                    let joints = [
+                        &mut self.arm0_joint,
+                        &mut self.arm1_joint,
+                        &mut self.arm2_joint,
+                        &mut self.arm3_joint,
+                    ];
+                    for ((channel, joint), target) in ARM_CHANNELS.iter().zip(joints).zip(pose) {
+                        if let Some(ramp) = ramps.get_mut(*channel) {
+                            ramp.jump_to(joint.position());
+                            ramp.set_target_at(*target, now);
+                            joint.reset(*target);
+                        }
+                    }
+                } else {
+                    pr_info!(logger, "pose {} is empty", slot);
+                }
+            }
+            if !pressed && dualsense_state[state] {
+                dualsense_state[state] = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::harness::{assert_golden, Harness};
+    use crate::ps5_dualsense::BUTTONS_DUALSENSE;
+    use crate::script::Script;
+
+    #[test]
+    fn golden_session() {
+        let harness = Harness::new();
+        let feedback = Rc::new(RefCell::new(ControllerFeedback::new_at(harness.clock().now())));
+        let mut robot = Robot2_2::new(harness.sink(TOPIC), feedback);
+        let script = Script::new()
+            .press(BUTTONS_DUALSENSE::L2, Duration::from_millis(500))
+            .wait(Duration::from_millis(100))
+            .hold(BUTTONS_DUALSENSE::SELECT)
+            .tap(BUTTONS_DUALSENSE::SQUARE)
+            .release(BUTTONS_DUALSENSE::SELECT)
+            .hold(BUTTONS_DUALSENSE::CROSS)
+            .press(BUTTONS_DUALSENSE::L2, Duration::from_millis(500))
+            .release(BUTTONS_DUALSENSE::CROSS)
+            .tap(BUTTONS_DUALSENSE::CIRCLE)
+            .tap(BUTTONS_DUALSENSE::SQUARE)
+            .wait(Duration::from_millis(600))
+            .tap(BUTTONS_DUALSENSE::TRIANGLE)
+            .tap(BUTTONS_DUALSENSE::TRIANGLE);
+        harness.run(&mut robot, &script);
+        assert_golden("robot2_2", &harness.transcript());
+    }
+}
@@ -0,0 +1,146 @@
+use std::time::{Duration, Instant};
+
+use crate::behaviour::{Behaviour, DualsenseState};
+use crate::commands::PointCommand;
+use crate::controller::ControllerState;
+use crate::log::{pr_info, Logger};
+use crate::motion::{publish_ramps_at, ChannelRamps, MotionProfile, ProfileShape, RAMP_PERIOD};
+use crate::p9n_interface::PlaystationInterface;
+use crate::point_channel::{ChannelKind, ChannelMap, PointChannel};
+use crate::sink::Sink;
+
+pub const TOPIC: &str = "/point_2_3";
+
+const GATE_PROFILE: MotionProfile = MotionProfile {
+    shape: ProfileShape::SCurve,
+    max_velocity: 200.0,
+    max_acceleration: 800.0,
+};
+
+/// Gate servo, command codes and two rollers on `/point_2_3`.
+pub struct Robot2_3 {
+    p9n: PlaystationInterface,
+    logger: Logger,
+    dualsense_state: [bool; 15],
+    channels: ChannelMap,
+    ramps: ChannelRamps,
+    publisher: Box<dyn Sink<PointCommand>>,
+}
+
+impl Robot2_3 {
+    pub fn new(publisher: Box<dyn Sink<PointCommand>>) -> Robot2_3 {
+        Robot2_3 {
+            p9n: PlaystationInterface::from_state(ControllerState::default()),
+            logger: Logger::new("p9n_interface_2024"),
+            dualsense_state: [false; 15],
+            channels: ChannelMap::new()
+                .declare(PointChannel::Md2, ChannelKind::Servo("gate"), 25..=125)
+                .declare(PointChannel::Md3, ChannelKind::Code("command"), -2..=3)
+                .declare(PointChannel::Md4, ChannelKind::Motor("left_roller"), -128..=127)
+                .declare(PointChannel::Md5, ChannelKind::Motor("right_roller"), -128..=127),
+            ramps: ChannelRamps::new().with(PointChannel::Md2, 25.0, GATE_PROFILE),
+            publisher,
+        }
+    }
+}
+
+impl Behaviour for Robot2_3 {
+    fn tick_period(&self) -> Option<Duration> {
+        Some(RAMP_PERIOD)
+    }
+
+    fn tick_at(&mut self, now: Instant) {
+        publish_ramps_at(&mut self.ramps, &mut self.channels, &*self.publisher, &self.logger, now);
+    }
+
+    fn on_state_at(&mut self, state: ControllerState, now: Instant) {
+        self.p9n.set_state(state);
+        let p9n = &self.p9n;
+        let logger = &self.logger;
+        let dualsense_state = &mut self.dualsense_state;
+        let channels = &mut self.channels;
+        let publisher = &*self.publisher;
+
+        if p9n.pressed_dpad_up() && !dualsense_state[DualsenseState::D_PAD_UP] {
+            pr_info!(logger, "up");
+            dualsense_state[DualsenseState::D_PAD_UP] = true;
+            self.ramps.set_target_at(PointChannel::Md2, if !p9n.pressed_cross() {25.0} else {125.0}, now);
+            let command = if !p9n.pressed_cross() {0} else {3};
+            let _ = channels.publish(&[(PointChannel::Md3, command)], publisher, logger);
+        }
+        if !p9n.pressed_dpad_up() && dualsense_state[DualsenseState::D_PAD_UP] {
+            pr_info!(logger, "reverse up");
+            dualsense_state[DualsenseState::D_PAD_UP] = false;
+        }
+        if p9n.pressed_dpad_left() && !dualsense_state[DualsenseState::D_PAD_LEFT] {
+            pr_info!(logger, "left");
+            dualsense_state[DualsenseState::D_PAD_LEFT] = true;
+            let command = if !p9n.pressed_cross() {1} else {-1};
+            let _ = channels.publish(&[(PointChannel::Md3, command)], publisher, logger);
+        }
+        if !p9n.pressed_dpad_left() && dualsense_state[DualsenseState::D_PAD_LEFT] {
+            pr_info!(logger, "reverse left");
+            dualsense_state[DualsenseState::D_PAD_LEFT] = false;
+        }
+        if p9n.pressed_dpad_right() && !dualsense_state[DualsenseState::D_PAD_RIGHT] {
+            pr_info!(logger, "right");
+            dualsense_state[DualsenseState::D_PAD_RIGHT] = true;
+            let command = if !p9n.pressed_cross() {2} else {-2};
+            let _ = channels.publish(&[(PointChannel::Md3, command)], publisher, logger);
+        }
+        if !p9n.pressed_dpad_right() && dualsense_state[DualsenseState::D_PAD_RIGHT] {
+            pr_info!(logger, "reverse right");
+            dualsense_state[DualsenseState::D_PAD_RIGHT] = false;
+        }
+        if p9n.pressed_l2() {
+            pr_info!(logger, "l2");
+            dualsense_state[DualsenseState::L2] = true;
+            let power = if !p9n.pressed_cross() {127} else {-128};
+            let _ = channels.publish(&[(PointChannel::Md4, power)], publisher, logger);
+        }
+        if !p9n.pressed_l2() && dualsense_state[DualsenseState::L2] {
+            pr_info!(logger, "reverse l2");
+            dualsense_state[DualsenseState::L2] = false;
+            let _ = channels.publish(&[(PointChannel::Md4, 0)], publisher, logger);
+        }
+        if p9n.pressed_r2() {
+            pr_info!(logger, "r2");
+            dualsense_state[DualsenseState::R2] = true;
+            let power = if !p9n.pressed_cross() {127} else {-128};
+            let _ = channels.publish(&[(PointChannel::Md5, power)], publisher, logger);
+        }
+        if !p9n.pressed_r2() && dualsense_state[DualsenseState::R2] {
+            pr_info!(logger, "reverse r2");
+            dualsense_state[DualsenseState::R2] = false;
+            let _ = channels.publish(&[(PointChannel::Md5, 0)], publisher, logger);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{assert_golden, Harness};
+    use crate::ps5_dualsense::{AXES_DUALSENSE, BUTTONS_DUALSENSE};
+    use crate::script::{Script, TAP};
+
+    #[test]
+    fn golden_session() {
+        let harness = Harness::new();
+        let mut robot = Robot2_3::new(harness.sink(TOPIC));
+        let script = Script::new()
+            .hold(BUTTONS_DUALSENSE::CROSS)
+            .push(AXES_DUALSENSE::DPAD_Y, 1.0, TAP)
+            .release(BUTTONS_DUALSENSE::CROSS)
+            .wait(Duration::from_millis(700))
+            .push(AXES_DUALSENSE::DPAD_X, 1.0, TAP)
+            .push(AXES_DUALSENSE::DPAD_X, -1.0, TAP)
+            .press(BUTTONS_DUALSENSE::L2, Duration::from_millis(60))
+            .hold(BUTTONS_DUALSENSE::CROSS)
+            .press(BUTTONS_DUALSENSE::R2, Duration::from_millis(60))
+            .release(BUTTONS_DUALSENSE::CROSS)
+            .wait(TAP);
+        harness.run(&mut robot, &script);
+        assert_golden("robot2_3", &harness.transcript());
+    }
+}
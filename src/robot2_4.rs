@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+use crate::behaviour::{Behaviour, DualsenseState};
+use crate::commands::PointCommand;
+use crate::controller::ControllerState;
+use crate::log::{pr_info, pr_warn, Logger};
+use crate::motion::{publish_ramps_at, ChannelRamps, MotionProfile, ProfileShape, RAMP_PERIOD};
+use crate::p9n_interface::PlaystationInterface;
+use crate::persist::{StartupPolicy, StateFile};
+use crate::point_channel::{ChannelKind, ChannelMap, PointChannel};
+use crate::sink::Sink;
+
+pub const TOPIC: &str = "/point_2_4";
+
+const ARM_PROFILE: MotionProfile = MotionProfile {
+    shape: ProfileShape::SCurve,
+    max_velocity: 200.0,
+    max_acceleration: 800.0,
+};
+
+/// Three ramped arm servos on `/point_2_4`.
+pub struct Robot2_4 {
+    p9n: PlaystationInterface,
+    logger: Logger,
+    dualsense_state: [bool; 15],
+    channels: ChannelMap,
+    ramps: ChannelRamps,
+    publisher: Box<dyn Sink<PointCommand>>,
+}
+
+impl Robot2_4 {
+    pub fn new(publisher: Box<dyn Sink<PointCommand>>) -> Robot2_4 {
+        Robot2_4 {
+            p9n: PlaystationInterface::from_state(ControllerState::default()),
+            logger: Logger::new("p9n_interface_2024"),
+            dualsense_state: [false; 15],
+            channels: ChannelMap::new()
+                .declare(PointChannel::Md0, ChannelKind::Servo("arm0"), 25..=125)
+                .declare(PointChannel::Md1, ChannelKind::Servo("arm1"), 25..=125)
+                .declare(PointChannel::Md3, ChannelKind::Servo("arm3"), 25..=125),
+            ramps: ChannelRamps::new()
+                .with(PointChannel::Md0, 80.0, ARM_PROFILE)
+                .with(PointChannel::Md1, 80.0, ARM_PROFILE)
+                .with(PointChannel::Md3, 80.0, ARM_PROFILE),
+            publisher,
+        }
+    }
+    /// Restores the arm from `state` according to `policy` and keeps saving
+    /// it there. A homing ramp starts at `now`.
+    pub fn with_state_file_at(mut self, state: StateFile, policy: StartupPolicy, now: Instant) -> Robot2_4 {
+        let logger = &self.logger;
+        pr_info!(logger, "actuator state file: {}", state.path().display());
+        self.channels = std::mem::take(&mut self.channels).persist_to(state);
+        match self.channels.restore() {
+            Ok(restored) => {
+                pr_info!(logger, "{:?} from {}", policy, self.channels.describe());
+                self.ramps.start_from_at(&self.channels, &restored, policy, now);
+            }
+            Err(e) => pr_warn!(logger, "could not read actuator state: {}", e),
+        }
+        self
+    }
+}
+
+impl Behaviour for Robot2_4 {
+    fn tick_period(&self) -> Option<Duration> {
+        Some(RAMP_PERIOD)
+    }
+
+    fn tick_at(&mut self, now: Instant) {
+        publish_ramps_at(&mut self.ramps, &mut self.channels, &*self.publisher, &self.logger, now);
+    }
+
+    fn on_state_at(&mut self, state: ControllerState, now: Instant) {
+        self.p9n.set_state(state);
+        let p9n = &self.p9n;
+        let logger = &self.logger;
+        let dualsense_state = &mut self.dualsense_state;
+        let ramps = &mut self.ramps;
+
+        if p9n.pressed_dpad_up() && !dualsense_state[DualsenseState::D_PAD_UP] {
+            dualsense_state[DualsenseState::D_PAD_UP] = true;
+            ramps.set_target_at(PointChannel::Md0, if !p9n.pressed_cross() {125.0} else {80.0}, now);
+            pr_info!(logger, "arm0 -> {}", ramps.get(PointChannel::Md0).unwrap().target());
+        }
+        if !p9n.pressed_dpad_up() && dualsense_state[DualsenseState::D_PAD_UP] {
+            dualsense_state[DualsenseState::D_PAD_UP] = false;
+            self.publisher.send(self.channels.command());
+        }
+        if p9n.pressed_l2() && !dualsense_state[DualsenseState::L2] {
+            dualsense_state[DualsenseState::L2] = true;
+            ramps.set_target_at(PointChannel::Md3, 125.0, now);
+            pr_info!(logger, "arm3 -> 125");
+        }
+        if !p9n.pressed_l2() && dualsense_state[DualsenseState::L2] {
+            dualsense_state[DualsenseState::L2] = false;
+            ramps.set_target_at(PointChannel::Md3, 80.0, now);
+            pr_info!(logger, "arm3 -> 80");
+        }
+        if p9n.pressed_r2() && !dualsense_state[DualsenseState::R2] {
+            dualsense_state[DualsenseState::R2] = true;
+            ramps.set_target_at(PointChannel::Md1, 25.0, now);
+            pr_info!(logger, "arm1 -> 25");
+        }
+        if !p9n.pressed_r2() && dualsense_state[DualsenseState::R2] {
+            dualsense_state[DualsenseState::R2] = false;
+            ramps.set_target_at(PointChannel::Md1, 80.0, now);
+            pr_info!(logger, "arm1 -> 80");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::harness::{assert_golden, Harness};
+    use crate::ps5_dualsense::{AXES_DUALSENSE, BUTTONS_DUALSENSE};
+    use crate::script::Script;
+
+    #[test]
+    fn golden_session() {
+        let harness = Harness::new();
+        let mut robot = Robot2_4::new(harness.sink(TOPIC));
+        let script = Script::new()
+            .push(AXES_DUALSENSE::DPAD_Y, 1.0, Duration::from_millis(600))
+            .press(BUTTONS_DUALSENSE::L2, Duration::from_millis(400))
+            .wait(Duration::from_millis(400))
+            .press(BUTTONS_DUALSENSE::R2, Duration::from_millis(200))
+            .wait(Duration::from_millis(600));
+        harness.run(&mut robot, &script);
+        assert_golden("robot2_4", &harness.transcript());
+    }
+}
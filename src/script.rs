@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use crate::controller::ControllerState;
+use crate::ps5_dualsense::{AXES_DUALSENSE, BUTTONS_DUALSENSE};
+
+/// How often a script repeats the current state, like a joy node with
+/// autorepeat.
+pub const SCRIPT_PERIOD: Duration = Duration::from_millis(20);
+/// How long `tap` holds a button, and the pause after it.
+pub const TAP: Duration = Duration::from_millis(100);
+
+/// A timeline of controller states, e.g. "press L2 for 500 ms" or "tap
+/// triangle twice". Builder methods change the current state; `wait` holds
+/// it for a while.
+#[derive(Debug, Clone)]
+pub struct Script {
+    period: Duration,
+    segments: Vec<(ControllerState, Duration)>,
+    current: ControllerState,
+}
+
+impl Script {
+    pub fn new() -> Script {
+        Script {
+            period: SCRIPT_PERIOD,
+            segments: Vec::new(),
+            current: ControllerState::default(),
+        }
+    }
+    pub fn with_period(mut self, period: Duration) -> Script {
+        self.period = period;
+        self
+    }
+    /// Presses `button` until `release`. L2 and R2 also pull their trigger
+    /// axis all the way.
+    pub fn hold(mut self, button: usize) -> Script {
+        set_button(&mut self.current, button, true);
+        self
+    }
+    pub fn release(mut self, button: usize) -> Script {
+        set_button(&mut self.current, button, false);
+        self
+    }
+    /// Replaces the whole current state, e.g. with one decoded from a Joy
+    /// message.
+    pub fn state(mut self, state: ControllerState) -> Script {
+        self.current = state;
+        self
+    }
+    pub fn axis(mut self, axis: usize, value: f32) -> Script {
+        self.current.axes[axis] = value;
+        self
+    }
+    /// Keeps the current state for `duration`.
+    pub fn wait(mut self, duration: Duration) -> Script {
+        self.segments.push((self.current.clone(), duration));
+        self
+    }
+    pub fn press(self, button: usize, duration: Duration) -> Script {
+        self.hold(button).wait(duration).release(button)
+    }
+    pub fn tap(self, button: usize) -> Script {
+        self.press(button, TAP).wait(TAP)
+    }
+    /// Pushes `axis` to `value` for `duration`, then centres it. The d-pad
+    /// is an axis pair, so this is also how d-pad presses are scripted.
+    pub fn push(self, axis: usize, value: f32, duration: Duration) -> Script {
+        self.axis(axis, value).wait(duration).axis(axis, 0.0)
+    }
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+    pub fn duration(&self) -> Duration {
+        self.segments.iter().map(|(_, duration)| *duration).sum()
+    }
+    /// The state at `offset`; the last state holds after the end.
+    pub fn state_at(&self, offset: Duration) -> &ControllerState {
+        let mut end = Duration::ZERO;
+        for (state, duration) in &self.segments {
+            end += *duration;
+            if offset < end {
+                return state;
+            }
+        }
+        &self.current
+    }
+    /// The state repeated every `period` over the whole script.
+    pub fn frames(&self) -> Vec<(Duration, ControllerState)> {
+        let duration = self.duration();
+        let mut frames = Vec::new();
+        let mut offset = Duration::ZERO;
+        while offset < duration && !self.period.is_zero() {
+            frames.push((offset, self.state_at(offset).clone()));
+            offset += self.period;
+        }
+        frames
+    }
+}
+
+impl Default for Script {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn set_button(state: &mut ControllerState, button: usize, pressed: bool) {
+    state.buttons[button] = pressed;
+    let trigger = match button {
+        BUTTONS_DUALSENSE::L2 => AXES_DUALSENSE::L2,
+        BUTTONS_DUALSENSE::R2 => AXES_DUALSENSE::R2,
+        _ => return,
+    };
+    state.axes[trigger] = if pressed { -1.0 } else { 1.0 };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeline() {
+        let script = Script::new()
+            .hold(BUTTONS_DUALSENSE::CROSS)
+            .press(BUTTONS_DUALSENSE::L2, Duration::from_millis(50))
+            .tap(BUTTONS_DUALSENSE::TRIANGLE);
+        assert_eq!(script.duration(), Duration::from_millis(250));
+        let frames = script.frames();
+        assert_eq!(frames.len(), 13);
+        let l2 = |state: &ControllerState| (state.buttons[BUTTONS_DUALSENSE::L2], state.axes[AXES_DUALSENSE::L2]);
+        assert_eq!(l2(&frames[2].1), (true, -1.0));
+        assert_eq!(l2(&frames[3].1), (false, 1.0));
+        assert!(frames[3].1.buttons[BUTTONS_DUALSENSE::TRIANGLE]);
+        assert!(!frames[8].1.buttons[BUTTONS_DUALSENSE::TRIANGLE]);
+        assert!(frames.iter().all(|(_, state)| state.buttons[BUTTONS_DUALSENSE::CROSS]));
+    }
+}
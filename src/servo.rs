@@ -0,0 +1,209 @@
+/// Angle to pulse-width mapping for one hobby servo on a `PointDrive` channel.
+/// Angles outside the calibrated range are clamped, never wrapped.
+#[derive(Debug, Clone)]
+pub struct Servo {
+    min_pulse: i32,
+    max_pulse: i32,
+    min_angle: f32,
+    max_angle: f32,
+    table: Option<Vec<(f32, i32)>>,
+    reversed: bool,
+}
+
+impl Servo {
+    pub fn new(min_pulse: i32, max_pulse: i32, min_angle: f32, max_angle: f32) -> Servo {
+        let (min_angle, max_angle) = finite_range(min_angle, max_angle);
+        Servo {
+            min_pulse: min_pulse.min(max_pulse),
+            max_pulse: min_pulse.max(max_pulse),
+            min_angle,
+            max_angle,
+            table: None,
+            reversed: false,
+        }
+    }
+    /// Replaces the linear mapping with piecewise-linear interpolation between
+    /// measured `(angle, pulse)` points.
+    pub fn with_table(mut self, table: &[(f32, i32)]) -> Servo {
+        let mut table: Vec<(f32, i32)> = table
+            .iter()
+            .copied()
+            .filter(|(angle, _)| angle.is_finite())
+            .collect();
+        table.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self.table = if table.is_empty() { None } else { Some(table) };
+        self
+    }
+    pub fn reversed(mut self, reversed: bool) -> Servo {
+        self.reversed = reversed;
+        self
+    }
+    pub fn min_pulse(&self) -> i32 {
+        self.min_pulse
+    }
+    pub fn max_pulse(&self) -> i32 {
+        self.max_pulse
+    }
+    pub fn pulse(&self, degree: f32) -> i32 {
+        let degree = if degree.is_nan() { self.min_angle } else { degree };
+        let mut degree = degree.clamp(self.min_angle, self.max_angle);
+        if self.reversed {
+            degree = self.max_angle - (degree - self.min_angle);
+        }
+        let pulse = match &self.table {
+            Some(table) => interpolate(table, degree),
+            None => {
+                let span = self.max_angle - self.min_angle;
+                let ratio = if span > 0.0 { (degree - self.min_angle) / span } else { 0.0 };
+                self.min_pulse as f32 + ratio * (self.max_pulse as f32 - self.min_pulse as f32)
+            }
+        };
+        (pulse.round() as i32).clamp(self.min_pulse, self.max_pulse)
+    }
+    /// Channel value for the `i8` fields of `PointDrive` (md2..md5).
+    pub fn pulse_i8(&self, degree: f32) -> i8 {
+        self.pulse(degree).clamp(i8::MIN as i32, i8::MAX as i32) as i8
+    }
+    /// Channel value for the `i16` fields of `PointDrive` (md0, md1).
+    pub fn pulse_i16(&self, degree: f32) -> i16 {
+        self.pulse(degree).clamp(i16::MIN as i32, i16::MAX as i32) as i16
+    }
+}
+
+/// Orders a calibration range. A non-finite bound falls back to the other
+/// one, and a range with no finite bound becomes 0..=0, so `clamp` on the
+/// result never panics.
+pub fn finite_range(a: f32, b: f32) -> (f32, f32) {
+    match (a.is_finite(), b.is_finite()) {
+        (true, true) => (a.min(b), a.max(b)),
+        (true, false) => (a, a),
+        (false, true) => (b, b),
+        (false, false) => (0.0, 0.0),
+    }
+}
+
+fn interpolate(table: &[(f32, i32)], degree: f32) -> f32 {
+    let (first_angle, first_pulse) = table[0];
+    let (last_angle, last_pulse) = table[table.len() - 1];
+    if degree <= first_angle {
+        return first_pulse as f32;
+    }
+    if degree >= last_angle {
+        return last_pulse as f32;
+    }
+    for pair in table.windows(2) {
+        let (a0, p0) = pair[0];
+        let (a1, p1) = pair[1];
+        if degree <= a1 {
+            if a1 - a0 <= 0.0 {
+                return p1 as f32;
+            }
+            return p0 as f32 + (degree - a0) / (a1 - a0) * (p1 as f32 - p0 as f32);
+        }
+    }
+    last_pulse as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn standard() -> Servo {
+        Servo::new(25, 125, 0.0, 180.0)
+    }
+
+    #[test]
+    fn matches_legacy_mapping() {
+        let servo = standard();
+        assert_eq!(servo.pulse_i8(0.0), 25);
+        assert_eq!(servo.pulse_i8(45.0), 50);
+        assert_eq!(servo.pulse_i8(135.0), 100);
+        assert_eq!(servo.pulse_i8(180.0), 125);
+    }
+
+    #[test]
+    fn clamps_out_of_range_angles() {
+        let servo = standard();
+        assert_eq!(servo.pulse_i8(-90.0), 25);
+        assert_eq!(servo.pulse_i8(1000.0), 125);
+        assert_eq!(servo.pulse_i8(f32::INFINITY), 125);
+        assert_eq!(servo.pulse_i8(f32::NEG_INFINITY), 25);
+        assert_eq!(servo.pulse_i8(f32::NAN), 25);
+        // The old u16 arithmetic overflowed here.
+        assert_eq!(servo.pulse_i8(13108.0), 125);
+    }
+
+    #[test]
+    fn saturates_channel_type() {
+        let servo = Servo::new(0, 1000, 0.0, 180.0);
+        assert_eq!(servo.pulse_i8(180.0), i8::MAX);
+        assert_eq!(servo.pulse_i16(180.0), 1000);
+        let servo = Servo::new(-1000, 0, 0.0, 180.0);
+        assert_eq!(servo.pulse_i8(0.0), i8::MIN);
+    }
+
+    #[test]
+    fn reversed_mirrors_range() {
+        let servo = standard().reversed(true);
+        assert_eq!(servo.pulse_i8(0.0), 125);
+        assert_eq!(servo.pulse_i8(180.0), 25);
+        assert_eq!(servo.pulse_i8(90.0), 75);
+    }
+
+    #[test]
+    fn table_interpolates_and_clamps() {
+        let servo = standard().with_table(&[(90.0, 70), (0.0, 30), (180.0, 120)]);
+        assert_eq!(servo.pulse_i8(0.0), 30);
+        assert_eq!(servo.pulse_i8(45.0), 50);
+        assert_eq!(servo.pulse_i8(90.0), 70);
+        assert_eq!(servo.pulse_i8(135.0), 95);
+        assert_eq!(servo.pulse_i8(180.0), 120);
+        let servo = standard().with_table(&[(0.0, 0), (180.0, 200)]);
+        assert_eq!(servo.pulse_i8(180.0), 125);
+    }
+
+    #[test]
+    fn degenerate_ranges_do_not_panic() {
+        let servo = Servo::new(50, 50, 90.0, 90.0);
+        assert_eq!(servo.pulse_i8(0.0), 50);
+        assert_eq!(servo.pulse_i8(180.0), 50);
+        let servo = Servo::new(125, 25, 180.0, 0.0);
+        assert_eq!(servo.pulse_i8(180.0), 125);
+        let servo = standard().with_table(&[(f32::NAN, 10)]);
+        assert_eq!(servo.pulse_i8(180.0), 125);
+    }
+
+    #[test]
+    fn non_finite_calibration_does_not_panic() {
+        let servo = Servo::new(25, 125, f32::NAN, f32::NAN);
+        assert_eq!(servo.pulse_i8(90.0), 25);
+        assert_eq!(servo.pulse_i8(f32::NAN), 25);
+        let servo = Servo::new(25, 125, f32::NAN, 180.0);
+        assert_eq!(servo.pulse_i8(0.0), 25);
+        let servo = Servo::new(25, 125, f32::NEG_INFINITY, f32::INFINITY);
+        assert_eq!(servo.pulse_i8(90.0), 25);
+    }
+
+    proptest! {
+        #[test]
+        fn pulse_stays_in_calibrated_range(degree in prop::num::f32::ANY) {
+            let pulse = standard().pulse_i8(degree);
+            prop_assert!((25..=125).contains(&pulse), "{} -> {}", degree, pulse);
+        }
+
+        #[test]
+        fn channel_casts_saturate(
+            pulses in (any::<i32>(), any::<i32>()),
+            angles in (prop::num::f32::ANY, prop::num::f32::ANY),
+            degree in prop::num::f32::ANY,
+        ) {
+            let servo = Servo::new(pulses.0, pulses.1, angles.0, angles.1);
+            let pulse = servo.pulse(degree);
+            prop_assert!((servo.min_pulse()..=servo.max_pulse()).contains(&pulse));
+            prop_assert_eq!(servo.pulse_i8(degree) as i32, pulse.clamp(i8::MIN as i32, i8::MAX as i32));
+            prop_assert_eq!(servo.pulse_i16(degree) as i32, pulse.clamp(i16::MIN as i32, i16::MAX as i32));
+        }
+    }
+}
@@ -0,0 +1,37 @@
+#[cfg(feature = "ros")]
+use safe_drive::{msg::TypeSupport, topic::publisher::Publisher};
+
+/// Somewhere to send messages: a publisher on the robot, a recorder in tests.
+pub trait Sink<T> {
+    /// Sends `msg`, dropping it on failure like `let _ = publisher.send(..)`.
+    fn send(&self, msg: &T);
+}
+
+#[cfg(feature = "ros")]
+impl<T: TypeSupport> Sink<T> for Publisher<T> {
+    fn send(&self, msg: &T) {
+        let _ = Publisher::send(self, msg);
+    }
+}
+
+/// A publisher of `M` that is handed `T`, such as an `MdCommand`, and
+/// converts it to the message only when sending.
+#[cfg(feature = "ros")]
+pub struct PublisherSink<T, M: TypeSupport> {
+    publisher: Publisher<M>,
+    convert: fn(&T) -> M,
+}
+
+#[cfg(feature = "ros")]
+impl<T, M: TypeSupport> PublisherSink<T, M> {
+    pub fn new(publisher: Publisher<M>, convert: fn(&T) -> M) -> PublisherSink<T, M> {
+        PublisherSink { publisher, convert }
+    }
+}
+
+#[cfg(feature = "ros")]
+impl<T, M: TypeSupport> Sink<T> for PublisherSink<T, M> {
+    fn send(&self, value: &T) {
+        let _ = self.publisher.send(&(self.convert)(value));
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::commands::SdCommand;
+
+/// Tracks the on/off state of every port on one solenoid driver address.
+/// Each method returns the `SdCommand` to send, or `None` when the port
+/// is already in the requested state.
+pub struct SolenoidBank {
+    address: u8,
+    ports: HashMap<u8, bool>,
+    pulses: HashMap<u8, Instant>,
+}
+
+impl SolenoidBank {
+    pub fn new(address: u8) -> SolenoidBank {
+        SolenoidBank {
+            address,
+            ports: HashMap::new(),
+            pulses: HashMap::new(),
+        }
+    }
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+    pub fn is_on(&self, port: u8) -> bool {
+        *self.ports.get(&port).unwrap_or(&false)
+    }
+    pub fn on(&mut self, port: u8) -> Option<SdCommand> {
+        self.pulses.remove(&port);
+        self.set(port, true)
+    }
+    pub fn off(&mut self, port: u8) -> Option<SdCommand> {
+        self.pulses.remove(&port);
+        self.set(port, false)
+    }
+    pub fn toggle(&mut self, port: u8) -> Option<SdCommand> {
+        if self.is_on(port) {
+            self.off(port)
+        } else {
+            self.on(port)
+        }
+    }
+    /// Turns the port on and schedules it to be turned off by `tick_at` once
+    /// `duration` has elapsed.
+    pub fn pulse_at(&mut self, port: u8, duration: Duration, now: Instant) -> Option<SdCommand> {
+        let msg = self.set(port, true);
+        self.pulses.insert(port, now + duration);
+        msg
+    }
+    /// Call from a selector wall timer. Returns the commands for pulses that
+    /// have expired since the last call.
+    pub fn tick_at(&mut self, now: Instant) -> Vec<SdCommand> {
+        let mut expired: Vec<u8> = self
+            .pulses
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(port, _)| *port)
+            .collect();
+        expired.sort();
+        expired
+            .into_iter()
+            .filter_map(|port| self.off(port))
+            .collect()
+    }
+    fn set(&mut self, port: u8, on: bool) -> Option<SdCommand> {
+        if self.is_on(port) == on && self.ports.contains_key(&port) {
+            return None;
+        }
+        self.ports.insert(port, on);
+        Some(SdCommand {
+            address: self.address,
+            port,
+            power1: if on {1000} else {0},
+        })
+    }
+}